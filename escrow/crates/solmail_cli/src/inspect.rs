@@ -0,0 +1,90 @@
+//! Decoding of dumped program accounts.
+
+use std::fs;
+use std::mem::size_of;
+use std::path::Path;
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use solmail_escrow::Campaign;
+
+/// Prints the first listed account type whose discriminator prefixes `$data`.
+macro_rules! print_account {
+    ($data:expr, [$($account:ident),* $(,)?]) => {
+        $(
+            if $data.starts_with(solmail_escrow::$account::DISCRIMINATOR) {
+                let account = solmail_escrow::$account::try_deserialize(&mut &$data[..])
+                    .map_err(|err| format!("failed to decode {}: {err}", stringify!($account)))?;
+                println!("{account:#?}");
+                return Ok(());
+            }
+        )*
+    };
+}
+
+/// Decode the account dumped to `path` and print its state.
+pub fn run(path: &Path) -> Result<(), String> {
+    let data = fs::read(path).map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+
+    print_account!(data, [
+        Escrow,
+        PlatformConfig,
+        ModerationRecord,
+        RecipientStats,
+        ClaimReceipt,
+        Thread,
+        GlobalStats,
+        Inbox,
+        ClaimDelegate,
+        EscrowTree,
+        Auction,
+        Bid,
+        GcTreasury,
+        GroupEscrow,
+        InsuranceFund,
+        InsurancePayout,
+        BountyPool,
+        PoolContribution,
+    ]);
+
+    if data.starts_with(Campaign::DISCRIMINATOR) {
+        return print_campaign(&data[Campaign::DISCRIMINATOR.len()..]);
+    }
+
+    Err("not an escrow program account (unknown discriminator)".to_string())
+}
+
+/// Campaigns are zero-copy and too large to print whole; only entries in use are listed.
+fn print_campaign(data: &[u8]) -> Result<(), String> {
+    let campaign: Campaign = data
+        .get(..size_of::<Campaign>())
+        .and_then(|bytes| bytemuck::try_pod_read_unaligned(bytes).ok())
+        .ok_or("failed to decode Campaign: account data too short")?;
+
+    println!("Campaign {{");
+    println!("    sender: {},", campaign.sender);
+    println!("    created_at: {},", campaign.created_at);
+    println!("    expires_at: {},", campaign.expires_at);
+    println!("    entry_count: {},", campaign.entry_count);
+    println!("    entries: [");
+    for (index, entry) in campaign
+        .entries
+        .iter()
+        .take(campaign.entry_count as usize)
+        .enumerate()
+    {
+        let released = campaign.released[index / 8] & (1 << (index % 8)) != 0;
+        let recipient_hash: String = entry
+            .recipient_hash
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        println!(
+            "        {index}: recipient_hash {recipient_hash}, amount {}, released {released},",
+            entry.amount
+        );
+    }
+    println!("    ],");
+    println!("}}");
+
+    Ok(())
+}
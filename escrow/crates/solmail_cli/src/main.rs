@@ -0,0 +1,60 @@
+//! `solmail-cli`: support and debugging tools for the escrow program.
+//!
+//! - `pda` derives the address of any program account from its seeds.
+//! - `inspect` decodes an account dumped with
+//!   `solana account <ADDRESS> --output-file <FILE>` and prints its state.
+
+mod inspect;
+mod pda;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Parser, Subcommand};
+
+use crate::pda::PdaCommand;
+
+#[derive(Parser)]
+#[command(name = "solmail-cli", about = "Support and debugging tools for the SolMail escrow program")]
+struct Cli {
+    /// Escrow program id, for deployments other than the default.
+    #[arg(long, global = true, default_value_t = solmail_escrow::ID)]
+    program_id: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Derive the address and bump of a program account.
+    #[command(subcommand)]
+    Pda(PdaCommand),
+    /// Decode and print a dumped program account.
+    Inspect {
+        /// Raw account data, as written by `solana account <ADDRESS> --output-file <FILE>`.
+        file: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Pda(command) => {
+            let (address, bump) = command.derive(&cli.program_id);
+            println!("{address} (bump {bump})");
+            Ok(())
+        }
+        Command::Inspect { file } => inspect::run(&file),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! PDA derivation mirroring the program's `seeds` constraints.
+
+use anchor_lang::prelude::Pubkey;
+use clap::Subcommand;
+
+/// Program accounts whose address can be derived.
+#[derive(Subcommand)]
+pub enum PdaCommand {
+    /// Escrow of one sender for one email thread.
+    Escrow {
+        #[arg(long)]
+        sender: Pubkey,
+        /// Thread id as 64 hex characters.
+        #[arg(long, value_parser = parse_hash)]
+        thread_id: [u8; 32],
+    },
+    /// Aggregate of every escrow referencing a thread.
+    Thread {
+        /// Thread id as 64 hex characters.
+        #[arg(long, value_parser = parse_hash)]
+        thread_id: [u8; 32],
+    },
+    /// Summary of a receiver's pending escrows (the default pubkey holds unbound escrows).
+    Inbox {
+        #[arg(long)]
+        receiver: Pubkey,
+    },
+    /// Reply history of a recipient.
+    RecipientStats {
+        #[arg(long)]
+        recipient: Pubkey,
+    },
+    /// Claim delegate of a recipient.
+    ClaimDelegate {
+        #[arg(long)]
+        recipient: Pubkey,
+    },
+    /// Receipt of an escrow's claim.
+    ClaimReceipt {
+        #[arg(long)]
+        escrow: Pubkey,
+        /// Unix timestamp the escrow was created at.
+        #[arg(long, allow_negative_numbers = true)]
+        created_at: i64,
+    },
+    /// Audit record of a moderator cancellation.
+    Moderation {
+        #[arg(long)]
+        escrow: Pubkey,
+        /// Unix timestamp the escrow was created at.
+        #[arg(long, allow_negative_numbers = true)]
+        created_at: i64,
+    },
+    /// Temporary wSOL vault of an escrow funded from wSOL.
+    WsolVault {
+        #[arg(long)]
+        escrow: Pubkey,
+    },
+    /// Compressed escrow tree of a sender.
+    EscrowTree {
+        #[arg(long)]
+        sender: Pubkey,
+    },
+    /// Bounty pool opened by a creator for one email thread.
+    BountyPool {
+        #[arg(long)]
+        creator: Pubkey,
+        /// Thread id as 64 hex characters.
+        #[arg(long, value_parser = parse_hash)]
+        thread_id: [u8; 32],
+    },
+    /// One contributor's share of a bounty pool.
+    PoolContribution {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        contributor: Pubkey,
+    },
+    /// Group escrow of one sender for one email thread.
+    GroupEscrow {
+        #[arg(long)]
+        sender: Pubkey,
+        /// Thread id as 64 hex characters.
+        #[arg(long, value_parser = parse_hash)]
+        thread_id: [u8; 32],
+    },
+    /// A recipient's inbox auction for one day.
+    Auction {
+        #[arg(long)]
+        recipient: Pubkey,
+        #[arg(long)]
+        day: u64,
+    },
+    /// A bidder's bid in an auction.
+    Bid {
+        #[arg(long)]
+        auction: Pubkey,
+        #[arg(long)]
+        bidder: Pubkey,
+    },
+    /// Audit record of an insurance payout.
+    InsurancePayout {
+        /// Claim id as 64 hex characters.
+        #[arg(long, value_parser = parse_hash)]
+        claim_id: [u8; 32],
+    },
+    /// Platform config.
+    Config,
+    /// Program-wide escrow totals.
+    GlobalStats,
+    /// Soulbound reputation badge mint.
+    BadgeMint,
+    /// Protocol insurance fund.
+    InsuranceFund,
+    /// Garbage collection bounty treasury.
+    GcTreasury,
+    /// Signer of `emit_cpi!` event self-invocations.
+    EventAuthority,
+}
+
+impl PdaCommand {
+    /// Seeds of the account, in the order the program declares them.
+    fn seeds(&self) -> Vec<Vec<u8>> {
+        match self {
+            PdaCommand::Escrow { sender, thread_id } => {
+                vec![b"escrow".to_vec(), sender.to_bytes().to_vec(), thread_id.to_vec()]
+            }
+            PdaCommand::Thread { thread_id } => vec![b"thread".to_vec(), thread_id.to_vec()],
+            PdaCommand::Inbox { receiver } => vec![b"inbox".to_vec(), receiver.to_bytes().to_vec()],
+            PdaCommand::RecipientStats { recipient } => {
+                vec![b"recipient_stats".to_vec(), recipient.to_bytes().to_vec()]
+            }
+            PdaCommand::ClaimDelegate { recipient } => {
+                vec![b"claim_delegate".to_vec(), recipient.to_bytes().to_vec()]
+            }
+            PdaCommand::ClaimReceipt { escrow, created_at } => vec![
+                b"claim_receipt".to_vec(),
+                escrow.to_bytes().to_vec(),
+                created_at.to_le_bytes().to_vec(),
+            ],
+            PdaCommand::Moderation { escrow, created_at } => vec![
+                b"moderation".to_vec(),
+                escrow.to_bytes().to_vec(),
+                created_at.to_le_bytes().to_vec(),
+            ],
+            PdaCommand::WsolVault { escrow } => {
+                vec![b"wsol_vault".to_vec(), escrow.to_bytes().to_vec()]
+            }
+            PdaCommand::EscrowTree { sender } => {
+                vec![b"escrow_tree".to_vec(), sender.to_bytes().to_vec()]
+            }
+            PdaCommand::BountyPool { creator, thread_id } => vec![
+                b"bounty_pool".to_vec(),
+                creator.to_bytes().to_vec(),
+                thread_id.to_vec(),
+            ],
+            PdaCommand::PoolContribution { pool, contributor } => vec![
+                b"pool_contribution".to_vec(),
+                pool.to_bytes().to_vec(),
+                contributor.to_bytes().to_vec(),
+            ],
+            PdaCommand::GroupEscrow { sender, thread_id } => vec![
+                b"group_escrow".to_vec(),
+                sender.to_bytes().to_vec(),
+                thread_id.to_vec(),
+            ],
+            PdaCommand::Auction { recipient, day } => vec![
+                b"auction".to_vec(),
+                recipient.to_bytes().to_vec(),
+                day.to_le_bytes().to_vec(),
+            ],
+            PdaCommand::Bid { auction, bidder } => vec![
+                b"bid".to_vec(),
+                auction.to_bytes().to_vec(),
+                bidder.to_bytes().to_vec(),
+            ],
+            PdaCommand::InsurancePayout { claim_id } => {
+                vec![b"insurance_payout".to_vec(), claim_id.to_vec()]
+            }
+            PdaCommand::Config => vec![b"config".to_vec()],
+            PdaCommand::GlobalStats => vec![b"global_stats".to_vec()],
+            PdaCommand::BadgeMint => vec![b"badge_mint".to_vec()],
+            PdaCommand::InsuranceFund => vec![b"insurance_fund".to_vec()],
+            PdaCommand::GcTreasury => vec![b"gc_treasury".to_vec()],
+            PdaCommand::EventAuthority => vec![b"__event_authority".to_vec()],
+        }
+    }
+
+    /// Address and bump of the account under `program_id`.
+    pub fn derive(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        let seeds = self.seeds();
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+
+        Pubkey::find_program_address(&seeds, program_id)
+    }
+}
+
+/// Parse a 32-byte hash written as 64 hex characters.
+fn parse_hash(value: &str) -> Result<[u8; 32], String> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    if value.len() != 64 || !value.is_ascii() {
+        return Err("expected 64 hex characters".to_string());
+    }
+
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(value.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|err| err.to_string())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| format!("invalid hex byte {pair:?}"))?;
+    }
+
+    Ok(hash)
+}
@@ -0,0 +1,292 @@
+//! Typed mapping of `solmail_escrow` error numbers.
+//!
+//! A failed instruction only surfaces a number, either as
+//! `InstructionError::Custom(code)` or in the `Error Number: <code>` log line
+//! Anchor writes. `SolmailError::from_code` resolves it back to the program's
+//! `EscrowError` or the Anchor `ErrorCode` it was raised as, so services can
+//! branch on `EscrowError::NotExpired` instead of matching log strings.
+
+use std::fmt;
+
+use anchor_lang::error::ErrorCode;
+use solmail_escrow::EscrowError;
+
+/// Label preceding the error number in Anchor's failure log line.
+const ERROR_NUMBER_LABEL: &str = "Error Number: ";
+
+/// An on-chain error number resolved to the error it was raised as.
+#[derive(Debug, Clone, Copy)]
+pub enum SolmailError {
+    /// Raised by the escrow program itself.
+    Escrow(EscrowError),
+    /// Raised by Anchor, typically a failed account constraint.
+    Anchor(ErrorCode),
+    /// Neither an escrow nor a known Anchor error number.
+    Unknown(u32),
+}
+
+/// Declares a lookup from error number to one of the listed variants.
+macro_rules! error_table {
+    ($lookup:ident, $error:ident, [$($variant:ident),* $(,)?]) => {
+        fn $lookup(code: u32) -> Option<$error> {
+            [$($error::$variant),*]
+                .into_iter()
+                .find(|error| u32::from(*error) == code)
+        }
+    };
+}
+
+error_table!(escrow_error, EscrowError, [
+    InvalidStatus,
+    ThreadIdMismatch,
+    SenderMismatch,
+    NotExpired,
+    InsufficientFunds,
+    ReceiverMismatch,
+    NoNewBadgeMilestone,
+    InvalidProof,
+    UnauthorizedCranker,
+    InvalidPriceFeed,
+    StalePrice,
+    InvalidTtl,
+    CampaignFull,
+    InvalidCampaignEntry,
+    ArithmeticOverflow,
+    Unauthorized,
+    InvalidAttestation,
+    InvalidAuctionSlots,
+    AuctionClosed,
+    AuctionOpen,
+    BidNotWinning,
+    DeadlinePassed,
+    TooManyParticipants,
+    ParticipantMismatch,
+    UnauthorizedDelegate,
+    InvalidReplyTerms,
+    InvalidBonus,
+    MetadataAlreadyCommitted,
+    ReplyNotVerified,
+    InvalidReplyPreimage,
+    QualityNotAttested,
+    InvalidBeneficiary,
+    RecipientNotBound,
+    AmountOutOfRange,
+    InvalidAmountLimits,
+    BatchAccountsMismatch,
+    EscrowAlreadyExists,
+    AlreadyUpgraded,
+    RetentionNotElapsed,
+    InvalidReplyWeight,
+    AlreadyExpired,
+    ClaimNotStale,
+]);
+
+// Instruction, constraint, require and account errors the program's accounts can
+// raise; token-extension constraints and IDL errors are left as `Unknown`.
+error_table!(anchor_error, ErrorCode, [
+    InstructionMissing,
+    InstructionFallbackNotFound,
+    InstructionDidNotDeserialize,
+    InstructionDidNotSerialize,
+    ConstraintMut,
+    ConstraintHasOne,
+    ConstraintSigner,
+    ConstraintRaw,
+    ConstraintOwner,
+    ConstraintRentExempt,
+    ConstraintSeeds,
+    ConstraintExecutable,
+    ConstraintClose,
+    ConstraintAddress,
+    ConstraintZero,
+    ConstraintTokenMint,
+    ConstraintTokenOwner,
+    ConstraintMintMintAuthority,
+    ConstraintMintFreezeAuthority,
+    ConstraintMintDecimals,
+    ConstraintSpace,
+    ConstraintAccountIsNone,
+    ConstraintTokenTokenProgram,
+    ConstraintMintTokenProgram,
+    ConstraintAssociatedTokenTokenProgram,
+    RequireViolated,
+    RequireEqViolated,
+    RequireKeysEqViolated,
+    RequireNeqViolated,
+    RequireKeysNeqViolated,
+    RequireGtViolated,
+    RequireGteViolated,
+    AccountDiscriminatorAlreadySet,
+    AccountDiscriminatorNotFound,
+    AccountDiscriminatorMismatch,
+    AccountDidNotDeserialize,
+    AccountDidNotSerialize,
+    AccountNotEnoughKeys,
+    AccountNotMutable,
+    AccountOwnedByWrongProgram,
+    InvalidProgramId,
+    InvalidProgramExecutable,
+    AccountNotSigner,
+    AccountNotSystemOwned,
+    AccountNotInitialized,
+    AccountNotProgramData,
+    AccountNotAssociatedTokenAccount,
+    AccountSysvarMismatch,
+    AccountReallocExceedsLimit,
+    AccountDuplicateReallocs,
+    DeclaredProgramIdMismatch,
+    TryingToInitPayerAsProgramAccount,
+    InvalidNumericConversion,
+]);
+
+impl SolmailError {
+    /// Resolve a custom program error number.
+    pub fn from_code(code: u32) -> Self {
+        escrow_error(code)
+            .map(SolmailError::Escrow)
+            .or_else(|| anchor_error(code).map(SolmailError::Anchor))
+            .unwrap_or(SolmailError::Unknown(code))
+    }
+
+    /// Resolve the error number of an Anchor failure log line, returning `None`
+    /// for lines that do not carry one.
+    pub fn from_log(line: &str) -> Option<Self> {
+        let (_, rest) = line.split_once(ERROR_NUMBER_LABEL)?;
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(rest, |end| &rest[..end]);
+
+        digits.parse().ok().map(Self::from_code)
+    }
+
+    /// The on-chain error number.
+    pub fn code(&self) -> u32 {
+        match self {
+            SolmailError::Escrow(error) => u32::from(*error),
+            SolmailError::Anchor(error) => u32::from(*error),
+            SolmailError::Unknown(code) => *code,
+        }
+    }
+
+    /// Name of the error variant, as declared on-chain.
+    pub fn name(&self) -> String {
+        match self {
+            SolmailError::Escrow(error) => error.name(),
+            SolmailError::Anchor(error) => error.name(),
+            SolmailError::Unknown(code) => format!("Unknown({code})"),
+        }
+    }
+}
+
+impl fmt::Display for SolmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolmailError::Escrow(error) => write!(f, "{error}"),
+            SolmailError::Anchor(error) => write!(f, "{error}"),
+            SolmailError::Unknown(code) => write!(f, "unknown error {code}"),
+        }
+    }
+}
+
+impl std::error::Error for SolmailError {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    /// `EscrowError` variants in declaration order, read from the program source.
+    fn declared_escrow_errors() -> Vec<String> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../programs/solmail_escrow/src/lib.rs");
+        let source = fs::read_to_string(path).unwrap();
+        let body = source
+            .split_once("#[error_code]\npub enum EscrowError {")
+            .and_then(|(_, rest)| rest.split_once('}'))
+            .map(|(body, _)| body)
+            .unwrap();
+
+        body.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("#[") && !line.starts_with("//"))
+            .map(|line| line.trim_end_matches(',').to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_escrow_error_code_resolves() {
+        let declared = declared_escrow_errors();
+        assert!(!declared.is_empty());
+
+        for (offset, variant) in declared.iter().enumerate() {
+            let code = 6000 + offset as u32;
+            let error = SolmailError::from_code(code);
+            assert!(matches!(error, SolmailError::Escrow(_)), "{variant} ({code}) is not mapped");
+            assert_eq!(&error.name(), variant);
+            assert_eq!(error.code(), code);
+        }
+
+        let past_the_end = 6000 + declared.len() as u32;
+        assert!(matches!(
+            SolmailError::from_code(past_the_end),
+            SolmailError::Unknown(code) if code == past_the_end
+        ));
+    }
+
+    #[test]
+    fn deployed_escrow_error_codes_are_unchanged() {
+        for (code, name) in [
+            (6000, "InvalidStatus"),
+            (6001, "ThreadIdMismatch"),
+            (6002, "SenderMismatch"),
+            (6003, "NotExpired"),
+            (6004, "InsufficientFunds"),
+        ] {
+            assert_eq!(SolmailError::from_code(code).name(), name);
+        }
+    }
+
+    #[test]
+    fn anchor_error_codes_round_trip() {
+        for error in [
+            ErrorCode::InstructionMissing,
+            ErrorCode::ConstraintSeeds,
+            ErrorCode::ConstraintAddress,
+            ErrorCode::RequireKeysEqViolated,
+            ErrorCode::AccountDidNotDeserialize,
+            ErrorCode::AccountNotSigner,
+            ErrorCode::InvalidNumericConversion,
+        ] {
+            let code = u32::from(error);
+            let resolved = SolmailError::from_code(code);
+            assert!(matches!(resolved, SolmailError::Anchor(_)), "{code} is not mapped");
+            assert_eq!(resolved.name(), error.name());
+            assert_eq!(resolved.code(), code);
+        }
+
+        for code in 0..6000 {
+            if let SolmailError::Anchor(error) = SolmailError::from_code(code) {
+                assert_eq!(u32::from(error), code);
+            }
+        }
+    }
+
+    #[test]
+    fn resolves_error_numbers_from_logs() {
+        let code = u32::from(EscrowError::NotExpired);
+        let line = format!(
+            "Program log: AnchorError occurred. Error Code: NotExpired. Error Number: {code}. \
+             Error Message: Escrow has not expired yet."
+        );
+
+        let error = SolmailError::from_log(&line).unwrap();
+        assert_eq!(error.name(), "NotExpired");
+        assert!(SolmailError::from_log("Program log: claimed").is_none());
+        assert!(matches!(
+            SolmailError::from_log("Error Number: 1"),
+            Some(SolmailError::Unknown(1))
+        ));
+    }
+}
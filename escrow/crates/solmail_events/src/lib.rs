@@ -0,0 +1,275 @@
+//! Typed decoding of `solmail_escrow` events.
+//!
+//! The program emits its events through `emit_cpi!`: a self-invocation whose
+//! instruction data is `EVENT_IX_TAG_LE || discriminator || borsh(event)`. Events
+//! written with `emit!` appear instead as `Program data: <base64>` log lines. Both
+//! forms decode into the same `EscrowEvent`, so the indexer, CLI and webhook daemon
+//! share one decoder.
+
+use std::fmt;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Prefix of the log lines carrying `emit!` events.
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Why an event payload could not be decoded.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The payload is not prefixed by any escrow event discriminator.
+    UnknownDiscriminator,
+    /// A `Program data:` log line is not valid base64.
+    InvalidBase64(base64::DecodeError),
+    /// The discriminator matched but the body does not deserialize as that event.
+    Malformed {
+        /// Name of the event the discriminator identified.
+        event: &'static str,
+        /// Underlying borsh error.
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownDiscriminator => write!(f, "unknown event discriminator"),
+            DecodeError::InvalidBase64(err) => write!(f, "invalid base64 event data: {err}"),
+            DecodeError::Malformed { event, source } => {
+                write!(f, "malformed {event} event: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::UnknownDiscriminator => None,
+            DecodeError::InvalidBase64(err) => Some(err),
+            DecodeError::Malformed { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Declares `EscrowEvent` with one variant per program event, named after it.
+macro_rules! escrow_events {
+    ($($name:ident),* $(,)?) => {
+        /// Every event emitted by the escrow program.
+        pub enum EscrowEvent {
+            $($name(solmail_escrow::$name),)*
+        }
+
+        impl EscrowEvent {
+            /// Decode `discriminator || borsh(event)`.
+            pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+                $(
+                    if let Some(body) = data.strip_prefix(solmail_escrow::$name::DISCRIMINATOR) {
+                        return solmail_escrow::$name::try_from_slice(body)
+                            .map(EscrowEvent::$name)
+                            .map_err(|source| DecodeError::Malformed {
+                                event: stringify!($name),
+                                source,
+                            });
+                    }
+                )*
+
+                Err(DecodeError::UnknownDiscriminator)
+            }
+
+            /// Name of the event, as declared in the program.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(EscrowEvent::$name(_) => stringify!($name),)*
+                }
+            }
+        }
+
+        /// One encoded instance of every event, with its name.
+        #[cfg(test)]
+        fn encoded_events() -> Vec<(&'static str, Vec<u8>)> {
+            vec![$((stringify!($name), tests::encode::<solmail_escrow::$name>()),)*]
+        }
+    };
+}
+
+escrow_events! {
+    // lib.rs
+    EscrowInitialized,
+    EscrowMetadataCommitted,
+    ReplyPredicateVerified,
+    ReplyQualityAttested,
+    EarlyReplyBonusAdded,
+    EscrowClaimed,
+    RecipientBound,
+    RecipientReassigned,
+    ClaimDelegateSet,
+    DisputeOpened,
+    DisputeResolved,
+    EscrowExpired,
+    ClaimStarted,
+    ClaimCancelled,
+    EscrowModerated,
+    RefundScheduled,
+    RefundBeneficiarySet,
+    EscrowExtended,
+    EscrowUpgraded,
+    EscrowRefunded,
+    CompressedEscrowAppended,
+    CompressedEscrowSettled,
+    // auction.rs
+    AuctionBidPlaced,
+    AuctionBidSettled,
+    // batch.rs
+    BatchItemFailed,
+    EscrowsReleased,
+    EscrowsRefunded,
+    // campaign.rs
+    CampaignEntriesReleased,
+    CampaignRefunded,
+    // gc.rs
+    EscrowCollected,
+    // group.rs
+    GroupReplyRecorded,
+    GroupEscrowSettled,
+    // insurance.rs
+    InsuranceContributed,
+    InsurancePaidOut,
+    // pool.rs
+    PoolContributed,
+    BountyPoolClaimed,
+    PoolContributionSettled,
+}
+
+impl EscrowEvent {
+    /// Decode an inner instruction, returning `None` unless it is an `emit_cpi!`
+    /// self-invocation of the escrow program.
+    pub fn from_inner_instruction(
+        program_id: &Pubkey,
+        data: &[u8],
+    ) -> Option<Result<Self, DecodeError>> {
+        if *program_id != solmail_escrow::ID {
+            return None;
+        }
+        let event = data.strip_prefix(EVENT_IX_TAG_LE)?;
+
+        Some(Self::decode(event))
+    }
+
+    /// Decode a transaction log line, returning `None` unless it is a `Program data:` line.
+    ///
+    /// Log lines do not name the emitting program; callers must only pass lines
+    /// logged while the escrow program was executing.
+    pub fn from_log(line: &str) -> Option<Result<Self, DecodeError>> {
+        let encoded = line.strip_prefix(PROGRAM_DATA_PREFIX)?;
+
+        Some(
+            STANDARD
+                .decode(encoded)
+                .map_err(DecodeError::InvalidBase64)
+                .and_then(|data| Self::decode(&data)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use anchor_lang::Event;
+
+    use super::*;
+
+    /// `discriminator || borsh(event)` for an all-zero instance of `E`.
+    pub(crate) fn encode<E: Event>() -> Vec<u8> {
+        let event = E::deserialize(&mut &[0u8; 1024][..]).unwrap();
+        event.data()
+    }
+
+    /// Append the names of the `#[event]` structs declared under `dir` to `names`.
+    fn collect_events(dir: &Path, names: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                collect_events(&path, names);
+                continue;
+            }
+            let source = fs::read_to_string(path).unwrap();
+            let mut lines = source.lines().map(str::trim);
+            while let Some(line) = lines.next() {
+                if line != "#[event]" {
+                    continue;
+                }
+                let declaration = lines.find(|line| line.starts_with("pub struct ")).unwrap();
+                let name = declaration["pub struct ".len()..]
+                    .split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .next()
+                    .unwrap();
+                names.push(name.to_string());
+            }
+        }
+    }
+
+    /// Names of every `#[event]` struct declared in the program source.
+    fn declared_events() -> Vec<String> {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../programs/solmail_escrow/src");
+        let mut names = Vec::new();
+        collect_events(&src, &mut names);
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn every_program_event_is_decoded() {
+        let mut decoded: Vec<String> = encoded_events()
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        decoded.sort();
+
+        assert_eq!(decoded, declared_events());
+    }
+
+    #[test]
+    fn events_round_trip() {
+        for (name, data) in encoded_events() {
+            let event = EscrowEvent::decode(&data).unwrap();
+            assert_eq!(event.name(), name);
+        }
+    }
+
+    #[test]
+    fn events_decode_from_logs_and_inner_instructions() {
+        let data = encode::<solmail_escrow::EscrowClaimed>();
+
+        let line = format!("{PROGRAM_DATA_PREFIX}{}", STANDARD.encode(&data));
+        let event = EscrowEvent::from_log(&line).unwrap().unwrap();
+        assert_eq!(event.name(), "EscrowClaimed");
+        assert!(EscrowEvent::from_log("Program log: claimed").is_none());
+
+        let ix_data = [EVENT_IX_TAG_LE, &data[..]].concat();
+        let event = EscrowEvent::from_inner_instruction(&solmail_escrow::ID, &ix_data)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.name(), "EscrowClaimed");
+        assert!(EscrowEvent::from_inner_instruction(&Pubkey::default(), &ix_data).is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_and_truncated_events() {
+        assert!(matches!(
+            EscrowEvent::decode(&[0; 16]),
+            Err(DecodeError::UnknownDiscriminator)
+        ));
+
+        let data = encode::<solmail_escrow::EscrowClaimed>();
+        assert!(matches!(
+            EscrowEvent::decode(&data[..data.len() - 1]),
+            Err(DecodeError::Malformed { event: "EscrowClaimed", .. })
+        ));
+    }
+}
@@ -0,0 +1,387 @@
+//! Inbox-placement auctions.
+//!
+//! A recipient auctions a limited number of "priority inbox" slots for a day.
+//! Senders bid escrowed lamports; the top `slots` bids at close win placement and
+//! stay claimable by the recipient on reply, while every other bid is refunded.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::{transfer_lamports, EscrowError, PlatformConfig};
+
+/// Maximum number of priority slots a single auction can offer.
+pub const MAX_AUCTION_SLOTS: usize = 10;
+
+/// Seconds after close during which the recipient can claim winning bids by replying.
+pub const AUCTION_REPLY_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+/// A recipient's auction for one day of priority inbox placement.
+#[account]
+#[derive(Debug)]
+pub struct Auction {
+    /// Wallet whose inbox slots are auctioned.
+    pub recipient: Pubkey,
+    /// Day index (unix timestamp / 86400) the slots apply to.
+    pub day: u64,
+    /// Number of slots on offer.
+    pub slots: u8,
+    /// Unix timestamp after which no bids are accepted.
+    pub closes_at: i64,
+    /// Current leading bids, sorted by amount descending.
+    pub leaders: Vec<LeadingBid>,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl Auction {
+    /// Size of the Auction account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // recipient
+        8 + // day
+        1 + // slots
+        8 + // closes_at
+        4 + MAX_AUCTION_SLOTS * LeadingBid::LEN + // leaders
+        1; // bump
+
+    fn is_leader(&self, bid: &Pubkey) -> bool {
+        self.leaders.iter().any(|leader| leader.bid == *bid)
+    }
+
+    /// End of the window in which winning bids can be claimed.
+    fn reply_deadline(&self) -> Result<i64> {
+        self.closes_at
+            .checked_add(AUCTION_REPLY_WINDOW)
+            .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+    }
+}
+
+/// A bid currently holding one of the auction's slots.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LeadingBid {
+    /// Bid PDA.
+    pub bid: Pubkey,
+    /// Lamports bid.
+    pub amount: u64,
+}
+
+impl LeadingBid {
+    pub const LEN: usize = 32 + 8;
+}
+
+/// A sender's escrowed bid in an auction.
+#[account]
+#[derive(Debug)]
+pub struct Bid {
+    /// Auction the bid belongs to.
+    pub auction: Pubkey,
+    /// Wallet that placed and funded the bid.
+    pub bidder: Pubkey,
+    /// Email thread the bid is for.
+    pub thread_id: [u8; 32],
+    /// Lamports bid.
+    pub amount: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl Bid {
+    /// Size of the Bid account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // auction
+        32 + // bidder
+        32 + // thread_id
+        8 + // amount
+        1; // bump
+}
+
+/// Accounts required to open an auction.
+#[derive(Accounts)]
+#[instruction(day: u64)]
+pub struct CreateAuction<'info> {
+    /// The recipient auctioning their inbox slots.
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// PDA holding the auction state.
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + Auction::LEN,
+        seeds = [b"auction", recipient.key().as_ref(), &day.to_le_bytes()],
+        bump,
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to place a bid.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    /// The sender placing the bid.
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// The auction being bid on.
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+
+    /// PDA holding the bid state and lamports.
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + Bid::LEN,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump,
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// System program for creating the account and transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required for the recipient to claim a winning bid.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinningBid<'info> {
+    /// The recipient claiming the bid.
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// The auction the bid won.
+    #[account(has_one = recipient)]
+    pub auction: Account<'info, Auction>,
+
+    /// The winning bid, closed to the bidder once paid out.
+    #[account(
+        mut,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = auction,
+        has_one = bidder,
+        close = bidder,
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// The bidder, receiving the bid account's rent.
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+}
+
+/// Accounts required to refund a bid that lost or went unclaimed.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseBid<'info> {
+    /// Anyone may crank the refund once it is due.
+    pub cranker: Signer<'info>,
+
+    /// The auction the bid was placed in.
+    pub auction: Account<'info, Auction>,
+
+    /// The bid, closed to the bidder with all its lamports.
+    #[account(
+        mut,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = auction,
+        has_one = bidder,
+        close = bidder,
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// The bidder receiving the refund.
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+}
+
+/// Emitted when a bid is placed.
+#[event]
+pub struct AuctionBidPlaced {
+    /// Auction being bid on.
+    pub auction: Pubkey,
+    /// PDA holding the bid state and lamports.
+    pub bid: Pubkey,
+    /// Wallet that placed the bid.
+    pub bidder: Pubkey,
+    /// Email thread the bid is for.
+    pub thread_id: [u8; 32],
+    /// Lamports bid.
+    pub amount: u64,
+    /// Whether the bid currently holds a slot.
+    pub leading: bool,
+}
+
+/// Emitted when a bid is paid to the recipient or refunded to the bidder.
+#[event]
+pub struct AuctionBidSettled {
+    /// Auction the bid was placed in.
+    pub auction: Pubkey,
+    /// The settled bid.
+    pub bid: Pubkey,
+    /// Wallet that placed the bid.
+    pub bidder: Pubkey,
+    /// Lamports bid.
+    pub amount: u64,
+    /// True if the recipient claimed the bid, false if it was refunded.
+    pub claimed: bool,
+}
+
+pub fn process_create_auction(
+    ctx: Context<CreateAuction>,
+    day: u64,
+    slots: u8,
+    closes_at: i64,
+) -> Result<()> {
+    let auction = &mut ctx.accounts.auction;
+    let clock = Clock::get()?;
+
+    require!(
+        slots > 0 && slots as usize <= MAX_AUCTION_SLOTS,
+        EscrowError::InvalidAuctionSlots
+    );
+    require!(
+        closes_at > clock.unix_timestamp,
+        EscrowError::AuctionClosed
+    );
+
+    auction.recipient = ctx.accounts.recipient.key();
+    auction.day = day;
+    auction.slots = slots;
+    auction.closes_at = closes_at;
+    auction.leaders = Vec::new();
+    auction.bump = ctx.bumps.auction;
+
+    Ok(())
+}
+
+pub fn process_place_bid(ctx: Context<PlaceBid>, thread_id: [u8; 32], amount: u64) -> Result<()> {
+    let auction = &mut ctx.accounts.auction;
+    let bid = &mut ctx.accounts.bid;
+    let clock = Clock::get()?;
+
+    require!(
+        clock.unix_timestamp < auction.closes_at,
+        EscrowError::AuctionClosed
+    );
+
+    // Verify the bid is within the protocol limits.
+    ctx.accounts.config.check_escrow_amount(amount)?;
+
+    bid.auction = auction.key();
+    bid.bidder = ctx.accounts.bidder.key();
+    bid.thread_id = thread_id;
+    bid.amount = amount;
+    bid.bump = ctx.bumps.bid;
+
+    // Insert into the leaderboard; ties keep the earlier bid ahead.
+    let position = auction
+        .leaders
+        .iter()
+        .position(|leader| amount > leader.amount)
+        .unwrap_or(auction.leaders.len());
+    let slots = auction.slots as usize;
+    let leading = position < slots;
+    if leading {
+        auction.leaders.insert(
+            position,
+            LeadingBid {
+                bid: bid.key(),
+                amount,
+            },
+        );
+        auction.leaders.truncate(slots);
+    }
+
+    // Transfer lamports from the bidder to the bid PDA.
+    let ix = system_instruction::transfer(&ctx.accounts.bidder.key(), &bid.key(), amount);
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.bidder.to_account_info(),
+            ctx.accounts.bid.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    emit_cpi!(AuctionBidPlaced {
+        auction: ctx.accounts.auction.key(),
+        bid: ctx.accounts.bid.key(),
+        bidder: ctx.accounts.bidder.key(),
+        thread_id,
+        amount,
+        leading,
+    });
+
+    Ok(())
+}
+
+pub fn process_claim_winning_bid(ctx: Context<ClaimWinningBid>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    let bid = &ctx.accounts.bid;
+    let clock = Clock::get()?;
+
+    // Winners are only known once bidding has closed, and stay claimable for the reply window.
+    require!(
+        clock.unix_timestamp >= auction.closes_at,
+        EscrowError::AuctionOpen
+    );
+    require!(
+        clock.unix_timestamp < auction.reply_deadline()?,
+        EscrowError::AuctionClosed
+    );
+    require!(
+        auction.is_leader(&bid.key()),
+        EscrowError::BidNotWinning
+    );
+
+    // Pay the bid to the recipient; the `close` constraint returns rent to the bidder.
+    transfer_lamports(
+        &ctx.accounts.bid.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        bid.amount,
+    )?;
+
+    emit_cpi!(AuctionBidSettled {
+        auction: auction.key(),
+        bid: bid.key(),
+        bidder: bid.bidder,
+        amount: bid.amount,
+        claimed: true,
+    });
+
+    Ok(())
+}
+
+pub fn process_close_bid(ctx: Context<CloseBid>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    let bid = &ctx.accounts.bid;
+    let clock = Clock::get()?;
+
+    // Losing bids are refundable at close; winners only once the reply window lapses.
+    let refundable_at = if auction.is_leader(&bid.key()) {
+        auction.reply_deadline()?
+    } else {
+        auction.closes_at
+    };
+    require!(
+        clock.unix_timestamp >= refundable_at,
+        EscrowError::AuctionOpen
+    );
+
+    // The `close` constraint returns the bid and its rent to the bidder.
+    emit_cpi!(AuctionBidSettled {
+        auction: auction.key(),
+        bid: bid.key(),
+        bidder: bid.bidder,
+        amount: bid.amount,
+        claimed: false,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,682 @@
+//! Batched escrow settlement.
+//!
+//! A recipient who replied to many paid emails, or their claim delegate, can
+//! release all of them in one transaction instead of signing one claim per
+//! escrow, each still leaving a `ClaimReceipt`, and a sender can refund
+//! every expired escrow of a campaign at once. Each escrow is passed as a group
+//! of remaining accounts and validated exactly like a single claim or refund.
+//!
+//! Batch instructions implement [`BatchOperation`] and run through [`run_batch`],
+//! which groups the remaining accounts, and in partial mode skips items that fail
+//! validation, reporting each one with a `BatchItemFailed` event.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::pyth::read_sol_usd_price;
+use crate::{
+    transfer_lamports, ClaimDelegate, ClaimReceipt, Escrow, EscrowClaimed, EscrowError,
+    EscrowRefunded, EscrowStatus, GlobalStats, Inbox, RecipientStats, Thread,
+};
+
+/// Remaining accounts passed per escrow to `release_many`: escrow, thread, sender,
+/// refund destination, claim receipt.
+pub const RELEASE_ACCOUNTS_PER_ESCROW: usize = 5;
+
+/// Remaining accounts passed per escrow to `refund_many`: escrow, thread, inbox, destination.
+pub const REFUND_ACCOUNTS_PER_ESCROW: usize = 4;
+
+/// An instruction applied to each group of remaining accounts in a batch.
+///
+/// Items are handled in two steps so partial batches stay consistent: `prepare`
+/// only reads and validates, and may fail without side effects, while `apply`
+/// moves funds and always aborts the whole transaction on error.
+pub trait BatchOperation<'info> {
+    /// Validated state handed from `prepare` to `apply`.
+    type Item;
+
+    /// Number of remaining accounts making up one item.
+    const ACCOUNTS_PER_ITEM: usize;
+
+    /// Validate one item without mutating any account.
+    fn prepare(&mut self, accounts: &'info [AccountInfo<'info>]) -> Result<Self::Item>;
+
+    /// Settle a prepared item, returning the lamports it moved.
+    fn apply(&mut self, item: Self::Item) -> Result<u64>;
+
+    /// Emit the event reporting an item skipped in a partial batch.
+    fn report_failure(&mut self, failure: BatchItemFailed) -> Result<()>;
+}
+
+/// Totals of a processed batch.
+pub struct BatchSummary {
+    /// Items settled.
+    pub succeeded: u32,
+    /// Items skipped because they failed validation.
+    pub failed: u32,
+    /// Lamports moved across settled items.
+    pub amount: u64,
+}
+
+/// Run `operation` over every group of `remaining_accounts`.
+///
+/// With `allow_partial`, items failing `prepare` are skipped and reported with a
+/// `BatchItemFailed` event; otherwise the first failure aborts the batch.
+pub fn run_batch<'info, O: BatchOperation<'info>>(
+    operation: &mut O,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    allow_partial: bool,
+) -> Result<BatchSummary> {
+    require!(
+        !remaining_accounts.is_empty() && remaining_accounts.len() % O::ACCOUNTS_PER_ITEM == 0,
+        EscrowError::BatchAccountsMismatch
+    );
+
+    let mut summary = BatchSummary {
+        succeeded: 0,
+        failed: 0,
+        amount: 0,
+    };
+    for (index, accounts) in remaining_accounts
+        .chunks(O::ACCOUNTS_PER_ITEM)
+        .enumerate()
+    {
+        let item = match operation.prepare(accounts) {
+            Ok(item) => item,
+            Err(error) if allow_partial => {
+                operation.report_failure(BatchItemFailed {
+                    index: index as u32,
+                    account: accounts[0].key(),
+                    error_code: u64::from(ProgramError::from(error)),
+                })?;
+                summary.failed = summary
+                    .failed
+                    .checked_add(1)
+                    .ok_or(EscrowError::ArithmeticOverflow)?;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        let amount = operation.apply(item)?;
+        summary.succeeded = summary
+            .succeeded
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        summary.amount = summary
+            .amount
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+    }
+
+    Ok(summary)
+}
+
+/// Accounts required for a receiver, or their claim delegate, to release several
+/// escrows at once.
+///
+/// Each escrow must follow as remaining accounts, in groups of
+/// `RELEASE_ACCOUNTS_PER_ESCROW`: the escrow, its thread, its sender, its refund
+/// destination (the escrow's payer, or its refund beneficiary), and the
+/// uninitialized `ClaimReceipt` PDA of the claim (all writable).
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseMany<'info> {
+    /// The receiver, or their claim delegate, signing the claims and paying for the receipts.
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    /// The receiver of every escrow in the batch.
+    #[account(mut)]
+    pub receiver: SystemAccount<'info>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", receiver.key().as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Reply history of the receiver, created on their first claim.
+    #[account(
+        init_if_needed,
+        payer = claimant,
+        space = 8 + RecipientStats::LEN,
+        seeds = [b"recipient_stats", receiver.key().as_ref()],
+        bump,
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: Pyth SOL/USD `PriceUpdateV2` account, required when the batch holds
+    /// USD-denominated escrows and validated in `read_sol_usd_price`.
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// The receiver's claim delegate, required when the claimant is not the receiver.
+    #[account(
+        seeds = [b"claim_delegate", receiver.key().as_ref()],
+        bump = claim_delegate.bump,
+    )]
+    pub claim_delegate: Option<Account<'info, ClaimDelegate>>,
+
+    /// System program for creating the stats and receipt accounts.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a sender to refund several expired escrows at once.
+///
+/// Each escrow must follow as remaining accounts, in groups of
+/// `REFUND_ACCOUNTS_PER_ESCROW`: the escrow, its thread, its receiver's inbox, and
+/// the refund destination (the escrow's payer, or its refund beneficiary), all writable.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundMany<'info> {
+    /// The sender who funded every escrow in the batch.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+/// Emitted for each batch item skipped because it failed validation.
+#[event]
+pub struct BatchItemFailed {
+    /// Position of the item in the batch.
+    pub index: u32,
+    /// First account of the item (the escrow).
+    pub account: Pubkey,
+    /// Program error code the item failed with.
+    pub error_code: u64,
+}
+
+/// Emitted once a batch of escrows has been released.
+#[event]
+pub struct EscrowsReleased {
+    /// Wallet the escrows were released to.
+    pub receiver: Pubkey,
+    /// Number of escrows released.
+    pub count: u32,
+    /// Number of escrows skipped in a partial batch.
+    pub failed: u32,
+    /// Lamports paid to the receiver across the batch.
+    pub amount: u64,
+}
+
+/// Emitted once a batch of expired escrows has been refunded.
+#[event]
+pub struct EscrowsRefunded {
+    /// Wallet that funded the refunded escrows.
+    pub sender: Pubkey,
+    /// Number of escrows refunded.
+    pub count: u32,
+    /// Number of escrows skipped in a partial batch.
+    pub failed: u32,
+    /// Lamports refunded across the batch.
+    pub amount: u64,
+}
+
+/// Create the `ClaimReceipt` PDA `receipt`, paid by `payer`.
+///
+/// Anyone may send lamports to the predictable address first, which makes
+/// `create_account` fail; like Anchor's `init`, a funded account is topped up to
+/// rent exemption, then allocated and assigned instead.
+fn create_receipt_account<'info>(
+    payer: &AccountInfo<'info>,
+    receipt: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let space = 8 + ClaimReceipt::LEN;
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(space);
+    let current_lamports = receipt.lamports();
+
+    if current_lamports == 0 {
+        let ix = system_instruction::create_account(
+            payer.key,
+            receipt.key,
+            rent_exempt_minimum,
+            space as u64,
+            &crate::ID,
+        );
+        return invoke_signed(
+            &ix,
+            &[payer.clone(), receipt.clone(), system_program.clone()],
+            &[seeds],
+        )
+        .map_err(Into::into);
+    }
+
+    let top_up = rent_exempt_minimum.saturating_sub(current_lamports);
+    if top_up > 0 {
+        let ix = system_instruction::transfer(payer.key, receipt.key, top_up);
+        invoke(&ix, &[payer.clone(), receipt.clone(), system_program.clone()])?;
+    }
+    let ix = system_instruction::allocate(receipt.key, space as u64);
+    invoke_signed(&ix, &[receipt.clone(), system_program.clone()], &[seeds])?;
+    let ix = system_instruction::assign(receipt.key, &crate::ID);
+    invoke_signed(&ix, &[receipt.clone(), system_program.clone()], &[seeds])?;
+
+    Ok(())
+}
+
+/// `release_many` as a batch operation.
+struct Release<'a, 'b, 'c, 'info> {
+    ctx: &'a mut Context<'b, 'c, 'info, 'info, ReleaseMany<'info>>,
+    clock: Clock,
+}
+
+/// A validated escrow ready to be released.
+struct ReleaseItem<'info> {
+    escrow: Account<'info, Escrow>,
+    thread: Account<'info, Thread>,
+    sender: &'info AccountInfo<'info>,
+    refund_destination: &'info AccountInfo<'info>,
+    claim_receipt: &'info AccountInfo<'info>,
+    receipt_bump: u8,
+    refund_share: u64,
+    forfeited_bonus: u64,
+    amount: u64,
+}
+
+impl<'info> BatchOperation<'info> for Release<'_, '_, '_, 'info> {
+    type Item = ReleaseItem<'info>;
+
+    const ACCOUNTS_PER_ITEM: usize = RELEASE_ACCOUNTS_PER_ESCROW;
+
+    fn prepare(&mut self, accounts: &'info [AccountInfo<'info>]) -> Result<Self::Item> {
+        let escrow = Account::<Escrow>::try_from(&accounts[0])?;
+        let thread = Account::<Thread>::try_from(&accounts[1])?;
+        let sender = &accounts[2];
+        let refund_destination = &accounts[3];
+        let claim_receipt = &accounts[4];
+
+        // Verify the escrow can still be claimed.
+        escrow.check_claimable(&self.clock)?;
+
+        // Verify the funds go to the receiver the sender bound the escrow to;
+        // open bounties need an attestation and are claimed one at a time.
+        require!(
+            escrow.receiver == self.ctx.accounts.receiver.key(),
+            EscrowError::ReceiverMismatch
+        );
+
+        // Verify the thread and sender accounts belong to the escrow.
+        require!(
+            thread.thread_id == escrow.thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+        require_keys_eq!(
+            sender.key(),
+            escrow.sender,
+            EscrowError::SenderMismatch
+        );
+
+        // Verify withheld shares go back to whoever funded the escrow.
+        require_keys_eq!(
+            refund_destination.key(),
+            escrow.refund_recipient(),
+            EscrowError::InvalidBeneficiary
+        );
+
+        // Verify the receipt is the claim's PDA, keyed by the escrow and its creation time.
+        let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+            &[
+                b"claim_receipt",
+                escrow.key().as_ref(),
+                &escrow.created_at.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+        require_keys_eq!(
+            claim_receipt.key(),
+            receipt_key,
+            ErrorCode::ConstraintSeeds
+        );
+
+        // Verify the receipt has not been created yet, so writing it cannot fail in `apply`.
+        require_keys_eq!(
+            *claim_receipt.owner,
+            system_program::ID,
+            ErrorCode::AccountNotSystemOwned
+        );
+
+        // Verify the reply satisfied the sender's content predicate, if one was set.
+        require!(
+            escrow.reply_commitment == [0; 32] || escrow.reply_verified,
+            EscrowError::ReplyNotVerified
+        );
+
+        // Verify the reply's quality score was attested, if the sender set a bar.
+        require!(
+            escrow.quality_bar == 0 || escrow.quality_attested,
+            EscrowError::QualityNotAttested
+        );
+
+        // Re-check the oracle staleness bounds for USD-denominated escrows.
+        if escrow.usd_cents > 0 {
+            let price_update = self
+                .ctx
+                .accounts
+                .price_update
+                .as_ref()
+                .ok_or(EscrowError::InvalidPriceFeed)?;
+            read_sol_usd_price(price_update, self.clock.unix_timestamp)?;
+        }
+
+        // Split the payout like `register_and_claim`: the fee buffer to the claimant,
+        // the withheld share to the refund destination, a forfeited bonus to the
+        // sender, and the rest to the receiver.
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(8 + Escrow::LEN);
+        let payout = escrow
+            .to_account_info()
+            .lamports()
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(EscrowError::InsufficientFunds)?
+            .checked_sub(escrow.claim_fee_buffer)
+            .ok_or(EscrowError::InsufficientFunds)?;
+        let refund_share = escrow.refund_share(payout, self.clock.unix_timestamp)?;
+        let forfeited_bonus = escrow.forfeited_bonus(self.clock.unix_timestamp);
+        let amount = payout
+            .checked_sub(refund_share)
+            .and_then(|amount| amount.checked_sub(forfeited_bonus))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        Ok(ReleaseItem {
+            escrow,
+            thread,
+            sender,
+            refund_destination,
+            claim_receipt,
+            receipt_bump,
+            refund_share,
+            forfeited_bonus,
+            amount,
+        })
+    }
+
+    fn apply(&mut self, item: Self::Item) -> Result<u64> {
+        let ReleaseItem {
+            mut escrow,
+            mut thread,
+            sender,
+            refund_destination,
+            claim_receipt,
+            receipt_bump,
+            refund_share,
+            forfeited_bonus,
+            amount,
+        } = item;
+        let now = self.clock.unix_timestamp;
+        let ctx = &mut *self.ctx;
+        let receiver_key = ctx.accounts.receiver.key();
+
+        ctx.accounts.recipient_stats.record_reply(
+            receiver_key,
+            &escrow,
+            now,
+            ctx.bumps.recipient_stats,
+        )?;
+
+        escrow.status.transition_to(EscrowStatus::Completed)?;
+        escrow.settled_at = now;
+        if escrow.claim_fee_buffer > 0 {
+            transfer_lamports(
+                &escrow.to_account_info(),
+                &ctx.accounts.claimant.to_account_info(),
+                escrow.claim_fee_buffer,
+            )?;
+        }
+        if refund_share > 0 {
+            transfer_lamports(&escrow.to_account_info(), refund_destination, refund_share)?;
+        }
+        if forfeited_bonus > 0 {
+            transfer_lamports(&escrow.to_account_info(), sender, forfeited_bonus)?;
+        }
+
+        let pending = escrow.pending_amount()?;
+        thread.settle(pending, amount)?;
+        thread.exit(&crate::ID)?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.global_stats.record_claim(amount)?;
+
+        // The payout goes to the receiver; the escrow keeps its rent until it is closed.
+        transfer_lamports(
+            &escrow.to_account_info(),
+            &ctx.accounts.receiver.to_account_info(),
+            amount,
+        )?;
+        escrow.exit(&crate::ID)?;
+
+        // Record the claim; batch releases carry no reply proof, so its hashes are zero.
+        let created_at = escrow.created_at.to_le_bytes();
+        let escrow_key = escrow.key();
+        let seeds: &[&[u8]] = &[b"claim_receipt", escrow_key.as_ref(), &created_at, &[receipt_bump]];
+        create_receipt_account(
+            &ctx.accounts.claimant.to_account_info(),
+            claim_receipt,
+            &ctx.accounts.system_program.to_account_info(),
+            seeds,
+        )?;
+        let receipt = ClaimReceipt {
+            escrow: escrow_key,
+            thread_id: escrow.thread_id,
+            claimer: ctx.accounts.claimant.key(),
+            receiver: receiver_key,
+            amount,
+            reply_proof_hash: [0; 32],
+            slot: self.clock.slot,
+            bump: receipt_bump,
+            reply_verdict_hash: [0; 32],
+        };
+        receipt.try_serialize(&mut &mut claim_receipt.try_borrow_mut_data()?[..])?;
+
+        emit_cpi!(EscrowClaimed {
+            escrow: escrow.key(),
+            sender: escrow.sender,
+            receiver: receiver_key,
+            thread_id: escrow.thread_id,
+            amount,
+            subject_hash: escrow.subject_hash,
+            attachment_hash: escrow.attachment_hash,
+        });
+
+        Ok(amount)
+    }
+
+    fn report_failure(&mut self, failure: BatchItemFailed) -> Result<()> {
+        let ctx = &*self.ctx;
+        emit_cpi!(failure);
+
+        Ok(())
+    }
+}
+
+/// `refund_many` as a batch operation.
+struct Refund<'a, 'b, 'c, 'info> {
+    ctx: &'a mut Context<'b, 'c, 'info, 'info, RefundMany<'info>>,
+    clock: Clock,
+}
+
+/// A validated expired escrow ready to be refunded.
+struct RefundItem<'info> {
+    escrow: Account<'info, Escrow>,
+    thread: Account<'info, Thread>,
+    inbox: Account<'info, Inbox>,
+    destination: &'info AccountInfo<'info>,
+    amount: u64,
+}
+
+impl<'info> BatchOperation<'info> for Refund<'_, '_, '_, 'info> {
+    type Item = RefundItem<'info>;
+
+    const ACCOUNTS_PER_ITEM: usize = REFUND_ACCOUNTS_PER_ESCROW;
+
+    fn prepare(&mut self, accounts: &'info [AccountInfo<'info>]) -> Result<Self::Item> {
+        let escrow = Account::<Escrow>::try_from(&accounts[0])?;
+        let thread = Account::<Thread>::try_from(&accounts[1])?;
+        let inbox = Account::<Inbox>::try_from(&accounts[2])?;
+        let destination = &accounts[3];
+
+        // Verify the escrow is pending or marked expired (not claimed, claiming or refunded).
+        require!(
+            escrow.status == EscrowStatus::Pending || escrow.status == EscrowStatus::Expired,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the sender matches.
+        require!(
+            escrow.sender == self.ctx.accounts.sender.key(),
+            EscrowError::SenderMismatch
+        );
+
+        // Verify the escrow has expired.
+        require!(
+            escrow.is_expired(&self.clock),
+            EscrowError::NotExpired
+        );
+
+        // Verify the thread, inbox and destination accounts belong to the escrow.
+        require!(
+            thread.thread_id == escrow.thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+        require!(
+            inbox.recipient == escrow.receiver,
+            EscrowError::ReceiverMismatch
+        );
+        require_keys_eq!(
+            destination.key(),
+            escrow.refund_recipient(),
+            EscrowError::InvalidBeneficiary
+        );
+
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(8 + Escrow::LEN);
+        let amount = escrow
+            .to_account_info()
+            .lamports()
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(EscrowError::InsufficientFunds)?;
+
+        Ok(RefundItem {
+            escrow,
+            thread,
+            inbox,
+            destination,
+            amount,
+        })
+    }
+
+    fn apply(&mut self, item: Self::Item) -> Result<u64> {
+        let RefundItem {
+            mut escrow,
+            mut thread,
+            mut inbox,
+            destination,
+            amount,
+        } = item;
+        let ctx = &mut *self.ctx;
+
+        escrow.status.transition_to(EscrowStatus::Refunded)?;
+        escrow.settled_at = self.clock.unix_timestamp;
+        let pending = escrow.pending_amount()?;
+        thread.settle(pending, 0)?;
+        thread.exit(&crate::ID)?;
+        inbox.settle(pending)?;
+        inbox.exit(&crate::ID)?;
+        ctx.accounts.global_stats.record_refund()?;
+
+        // The refund goes to the destination; the escrow keeps its rent until it is closed.
+        transfer_lamports(&escrow.to_account_info(), destination, amount)?;
+        escrow.exit(&crate::ID)?;
+
+        emit_cpi!(EscrowRefunded {
+            escrow: escrow.key(),
+            sender: escrow.sender,
+            thread_id: escrow.thread_id,
+            amount,
+            beneficiary: destination.key(),
+        });
+
+        Ok(amount)
+    }
+
+    fn report_failure(&mut self, failure: BatchItemFailed) -> Result<()> {
+        let ctx = &*self.ctx;
+        emit_cpi!(failure);
+
+        Ok(())
+    }
+}
+
+pub fn process_release_many<'info>(
+    mut ctx: Context<'_, '_, 'info, 'info, ReleaseMany<'info>>,
+    allow_partial: bool,
+) -> Result<()> {
+    // Verify the claimant is the receiver or the delegate they designated.
+    let claimant = ctx.accounts.claimant.key();
+    if claimant != ctx.accounts.receiver.key() {
+        let claim_delegate = ctx
+            .accounts
+            .claim_delegate
+            .as_ref()
+            .ok_or(EscrowError::UnauthorizedDelegate)?;
+        require_keys_eq!(
+            claimant,
+            claim_delegate.delegate,
+            EscrowError::UnauthorizedDelegate
+        );
+    }
+
+    let remaining_accounts = ctx.remaining_accounts;
+    let mut release = Release {
+        ctx: &mut ctx,
+        clock: Clock::get()?,
+    };
+    let summary = run_batch(&mut release, remaining_accounts, allow_partial)?;
+
+    emit_cpi!(EscrowsReleased {
+        receiver: ctx.accounts.receiver.key(),
+        count: summary.succeeded,
+        failed: summary.failed,
+        amount: summary.amount,
+    });
+
+    Ok(())
+}
+
+pub fn process_refund_many<'info>(
+    mut ctx: Context<'_, '_, 'info, 'info, RefundMany<'info>>,
+    allow_partial: bool,
+) -> Result<()> {
+    let remaining_accounts = ctx.remaining_accounts;
+    let mut refund = Refund {
+        ctx: &mut ctx,
+        clock: Clock::get()?,
+    };
+    let summary = run_batch(&mut refund, remaining_accounts, allow_partial)?;
+
+    emit_cpi!(EscrowsRefunded {
+        sender: ctx.accounts.sender.key(),
+        count: summary.succeeded,
+        failed: summary.failed,
+        amount: summary.amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,282 @@
+//! Zero-copy batch escrows for campaign sends.
+//!
+//! A `Campaign` packs up to `MAX_CAMPAIGN_ENTRIES` per-recipient escrows into one
+//! large account holding all of their lamports, so newsletters and recruiting
+//! blasts pay rent once and settle in a handful of transactions.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+use solana_sha256_hasher::hashv;
+
+use crate::{expiry, transfer_lamports, EscrowError, PlatformConfig};
+
+/// Maximum number of recipients in a single campaign.
+pub const MAX_CAMPAIGN_ENTRIES: usize = 512;
+
+/// Campaign account holding every per-recipient escrow entry.
+///
+/// The account is too large to be created through CPI, so clients allocate it
+/// (owned by this program) in the same transaction as `initialize_campaign`.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct Campaign {
+    /// Wallet that funded the campaign.
+    pub sender: Pubkey,
+    /// Unix timestamp when the campaign was created.
+    pub created_at: i64,
+    /// Unix timestamp after which the sender can refund unclaimed entries.
+    pub expires_at: i64,
+    /// Number of entries in use.
+    pub entry_count: u32,
+    pub _padding: [u8; 4],
+    /// Bit `i` is set once entry `i` has been released.
+    pub released: [u8; MAX_CAMPAIGN_ENTRIES / 8],
+    /// Per-recipient escrow entries.
+    pub entries: [CampaignEntry; MAX_CAMPAIGN_ENTRIES],
+}
+
+/// A single recipient's escrow inside a campaign.
+#[zero_copy]
+#[repr(C)]
+pub struct CampaignEntry {
+    /// SHA-256 of the recipient's wallet address.
+    pub recipient_hash: [u8; 32],
+    /// Amount of lamports escrowed for the recipient.
+    pub amount: u64,
+}
+
+/// Entry supplied by the sender when adding recipients to a campaign.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CampaignEntryInput {
+    pub recipient_hash: [u8; 32],
+    pub amount: u64,
+}
+
+impl Campaign {
+    fn is_released(&self, index: usize) -> bool {
+        self.released[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    fn mark_released(&mut self, index: usize) {
+        self.released[index / 8] |= 1 << (index % 8);
+    }
+}
+
+/// Hash a recipient wallet the way campaign entries store it.
+pub fn recipient_hash(recipient: &Pubkey) -> [u8; 32] {
+    hashv(&[recipient.as_ref()]).to_bytes()
+}
+
+/// Accounts required to initialize a campaign.
+#[derive(Accounts)]
+pub struct InitializeCampaign<'info> {
+    /// The sender funding the campaign.
+    pub sender: Signer<'info>,
+
+    /// Pre-allocated, zeroed campaign account owned by this program.
+    #[account(zero)]
+    pub campaign: AccountLoader<'info, Campaign>,
+}
+
+/// Accounts required to add entries to a campaign.
+#[derive(Accounts)]
+pub struct AddCampaignEntries<'info> {
+    /// The sender funding the campaign.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The campaign receiving the entries and their lamports.
+    #[account(mut, has_one = sender)]
+    pub campaign: AccountLoader<'info, Campaign>,
+
+    /// System program for transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required for a recipient to release their campaign entries.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseCampaignEntries<'info> {
+    /// The recipient claiming the funds.
+    #[account(mut)]
+    pub receiver: Signer<'info>,
+
+    /// The campaign holding the entries.
+    #[account(mut)]
+    pub campaign: AccountLoader<'info, Campaign>,
+}
+
+/// Accounts required to refund and close an expired campaign.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundCampaign<'info> {
+    /// The sender who funded the campaign (only they can refund).
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The campaign, closed to the sender once refunded.
+    #[account(mut, has_one = sender, close = sender)]
+    pub campaign: AccountLoader<'info, Campaign>,
+}
+
+/// Emitted when a recipient releases one or more campaign entries.
+#[event]
+pub struct CampaignEntriesReleased {
+    /// Campaign holding the entries.
+    pub campaign: Pubkey,
+    /// Wallet the entries were paid to.
+    pub receiver: Pubkey,
+    /// Positions of the released entries in the campaign.
+    pub indices: Vec<u16>,
+    /// Total lamports paid out to the receiver.
+    pub amount: u64,
+}
+
+/// Emitted when an expired campaign is refunded and closed.
+#[event]
+pub struct CampaignRefunded {
+    /// Campaign being refunded.
+    pub campaign: Pubkey,
+    /// Wallet that funded the campaign.
+    pub sender: Pubkey,
+    /// Unclaimed lamports returned to the sender (excluding rent).
+    pub amount: u64,
+}
+
+pub fn process_initialize_campaign(
+    ctx: Context<InitializeCampaign>,
+    ttl_seconds: i64,
+) -> Result<()> {
+    let mut campaign = ctx.accounts.campaign.load_init()?;
+    let clock = Clock::get()?;
+
+    campaign.sender = ctx.accounts.sender.key();
+    campaign.created_at = clock.unix_timestamp;
+    campaign.expires_at = expiry(clock.unix_timestamp, ttl_seconds)?;
+
+    Ok(())
+}
+
+pub fn process_add_campaign_entries(
+    ctx: Context<AddCampaignEntries>,
+    entries: Vec<CampaignEntryInput>,
+) -> Result<()> {
+    let mut campaign = ctx.accounts.campaign.load_mut()?;
+    let start = campaign.entry_count as usize;
+
+    require!(
+        start + entries.len() <= MAX_CAMPAIGN_ENTRIES,
+        EscrowError::CampaignFull
+    );
+
+    // Append the entries and total up the lamports they escrow.
+    let mut total: u64 = 0;
+    for (offset, entry) in entries.iter().enumerate() {
+        // Verify each entry, an escrow of its own, is within the protocol limits.
+        ctx.accounts.config.check_escrow_amount(entry.amount)?;
+
+        campaign.entries[start + offset] = CampaignEntry {
+            recipient_hash: entry.recipient_hash,
+            amount: entry.amount,
+        };
+        total = total
+            .checked_add(entry.amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+    }
+    campaign.entry_count += entries.len() as u32;
+    drop(campaign);
+
+    // Transfer lamports from the sender to the campaign account.
+    let ix = system_instruction::transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.campaign.key(),
+        total,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.sender.to_account_info(),
+            ctx.accounts.campaign.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn process_release_campaign_entries(
+    ctx: Context<ReleaseCampaignEntries>,
+    indices: Vec<u16>,
+) -> Result<()> {
+    let mut campaign = ctx.accounts.campaign.load_mut()?;
+    let receiver_hash = recipient_hash(&ctx.accounts.receiver.key());
+
+    // Every entry must be live, unreleased, and addressed to the receiver.
+    let mut total: u64 = 0;
+    for &index in &indices {
+        let index = index as usize;
+        require!(
+            index < campaign.entry_count as usize,
+            EscrowError::InvalidCampaignEntry
+        );
+        require!(
+            !campaign.is_released(index),
+            EscrowError::InvalidStatus
+        );
+        require!(
+            campaign.entries[index].recipient_hash == receiver_hash,
+            EscrowError::ReceiverMismatch
+        );
+
+        campaign.mark_released(index);
+        total = total
+            .checked_add(campaign.entries[index].amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+    }
+    drop(campaign);
+
+    // Pay the receiver out of the campaign's pooled lamports.
+    transfer_lamports(
+        &ctx.accounts.campaign.to_account_info(),
+        &ctx.accounts.receiver.to_account_info(),
+        total,
+    )?;
+
+    emit_cpi!(CampaignEntriesReleased {
+        campaign: ctx.accounts.campaign.key(),
+        receiver: ctx.accounts.receiver.key(),
+        indices,
+        amount: total,
+    });
+
+    Ok(())
+}
+
+pub fn process_refund_campaign(ctx: Context<RefundCampaign>) -> Result<()> {
+    let campaign = ctx.accounts.campaign.load()?;
+    let clock = Clock::get()?;
+
+    // Verify the campaign has expired.
+    require!(
+        clock.unix_timestamp >= campaign.expires_at,
+        EscrowError::NotExpired
+    );
+
+    // Everything not yet released goes back to the sender along with the rent.
+    let unclaimed = (0..campaign.entry_count as usize)
+        .filter(|&index| !campaign.is_released(index))
+        .try_fold(0u64, |total, index| total.checked_add(campaign.entries[index].amount))
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    emit_cpi!(CampaignRefunded {
+        campaign: ctx.accounts.campaign.key(),
+        sender: campaign.sender,
+        amount: unclaimed,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Merkle-tree helpers for compressed escrows.
+//!
+//! A compressed escrow is a leaf in a per-sender merkle tree. Only the root is
+//! stored on-chain; clients keep the leaves (rebuilt from `CompressedEscrowAppended`
+//! events) and supply a proof whenever a leaf is appended, claimed, or refunded.
+
+use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
+
+use crate::EscrowError;
+
+/// Depth of every escrow tree, allowing 2^16 compressed escrows per sender.
+pub const TREE_DEPTH: usize = 16;
+
+/// Leaf value of an empty or settled slot.
+pub const EMPTY_LEAF: [u8; 32] = [0; 32];
+
+/// Hash the fields of a compressed escrow into its leaf value.
+pub fn escrow_leaf(
+    sender: &Pubkey,
+    receiver: &Pubkey,
+    thread_id: &[u8; 32],
+    amount: u64,
+    expires_at: i64,
+) -> [u8; 32] {
+    hashv(&[
+        sender.as_ref(),
+        receiver.as_ref(),
+        thread_id,
+        &amount.to_le_bytes(),
+        &expires_at.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Root of a tree whose leaves are all empty.
+pub fn empty_root() -> [u8; 32] {
+    (0..TREE_DEPTH).fold(EMPTY_LEAF, |node, _| hash_pair(&node, &node))
+}
+
+/// Recompute the root from a leaf, its index, and its sibling path.
+pub fn compute_root(leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> Result<[u8; 32]> {
+    require!(proof.len() == TREE_DEPTH, EscrowError::InvalidProof);
+    require!((index as u64) < 1 << TREE_DEPTH, EscrowError::InvalidProof);
+
+    let root = proof.iter().enumerate().fold(leaf, |node, (level, sibling)| {
+        if index >> level & 1 == 0 {
+            hash_pair(&node, sibling)
+        } else {
+            hash_pair(sibling, &node)
+        }
+    });
+    Ok(root)
+}
+
+/// Verify `current_leaf` sits at `index` under `root`, then return the root with
+/// the leaf replaced by `new_leaf`.
+pub fn replace_leaf(
+    root: &[u8; 32],
+    current_leaf: [u8; 32],
+    new_leaf: [u8; 32],
+    index: u32,
+    proof: &[[u8; 32]],
+) -> Result<[u8; 32]> {
+    require!(
+        compute_root(current_leaf, index, proof)? == *root,
+        EscrowError::InvalidProof
+    );
+    compute_root(new_leaf, index, proof)
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[left, right]).to_bytes()
+}
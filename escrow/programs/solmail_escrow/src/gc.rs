@@ -0,0 +1,354 @@
+//! Garbage collection of settled escrows.
+//!
+//! Claimed and refunded escrows stay on-chain in their terminal status for
+//! `ESCROW_RETENTION_SECONDS`, so disputes and indexers can still read them.
+//! Past that window anyone may close them: the rent goes back to the payer
+//! (or the refund beneficiary) and the cranker earns `GC_BOUNTY_LAMPORTS`
+//! per account from the `GcTreasury`, so cleanup does not rely on platform keys.
+//! Once the treasury runs dry escrows are still collected, without a bounty.
+//! Crankers collecting their own escrows, as the sender or the rent destination,
+//! earn no bounty either, so the treasury cannot be farmed with self-made escrows.
+//!
+//! The sender, payer or refund beneficiary may also close a settled escrow themselves at
+//! any time with `close_settled_escrow`, reclaiming its rent without a bounty.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::batch::{run_batch, BatchItemFailed, BatchOperation};
+use crate::{close_account, transfer_lamports, Escrow, EscrowError, EscrowStatus};
+
+/// Seconds a settled escrow is kept before it can be garbage collected (30 days).
+pub const ESCROW_RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// Lamports paid to the cranker for each escrow closed.
+pub const GC_BOUNTY_LAMPORTS: u64 = 5_000;
+
+/// Remaining accounts passed per escrow to `gc_closed_escrows`: escrow, rent destination.
+pub const GC_ACCOUNTS_PER_ESCROW: usize = 2;
+
+/// Singleton treasury funding garbage collection bounties.
+#[account]
+#[derive(Debug)]
+pub struct GcTreasury {
+    /// Lamports ever funded into the treasury.
+    pub total_funded: u64,
+    /// Lamports ever paid out as bounties.
+    pub total_paid: u64,
+    /// Number of escrows garbage collected.
+    pub collected_count: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl GcTreasury {
+    /// Size of the GcTreasury account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        8 + // total_funded
+        8 + // total_paid
+        8 + // collected_count
+        1; // bump
+
+    /// Lamports funded and not yet paid out.
+    fn available(&self) -> Result<u64> {
+        self.total_funded
+            .checked_sub(self.total_paid)
+            .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+    }
+}
+
+/// Accounts required to create the garbage collection treasury.
+#[derive(Accounts)]
+pub struct InitializeGcTreasury<'info> {
+    /// Pays for the treasury account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Singleton PDA holding the bounty lamports.
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GcTreasury::LEN,
+        seeds = [b"gc_treasury"],
+        bump,
+    )]
+    pub treasury: Account<'info, GcTreasury>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to fund the garbage collection treasury.
+#[derive(Accounts)]
+pub struct FundGcTreasury<'info> {
+    /// The wallet funding bounties.
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    /// The garbage collection treasury.
+    #[account(
+        mut,
+        seeds = [b"gc_treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, GcTreasury>,
+
+    /// System program for transferring lamports.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to close settled escrows past their retention window.
+///
+/// Each escrow must follow as remaining accounts, in groups of
+/// `GC_ACCOUNTS_PER_ESCROW`: the escrow and its rent destination (the escrow's
+/// payer, or its refund beneficiary), both writable.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GcClosedEscrows<'info> {
+    /// Anyone may collect, earning the bounty for escrows they did not send or fund.
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// The treasury paying the bounties.
+    #[account(
+        mut,
+        seeds = [b"gc_treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, GcTreasury>,
+}
+
+/// Accounts required for the sender, payer or refund beneficiary to close a settled escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct CloseSettledEscrow<'info> {
+    /// The escrow's sender, or the wallet its rent returns to.
+    pub authority: Signer<'info>,
+
+    /// The claimed or refunded escrow, closed to the rent destination.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+        close = rent_destination,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// CHECK: the escrow's payer, or its refund beneficiary; verified in
+    /// `close_settled_escrow`.
+    #[account(mut)]
+    pub rent_destination: UncheckedAccount<'info>,
+}
+
+/// Emitted when a settled escrow is garbage collected or closed by its funder.
+#[event]
+pub struct EscrowCollected {
+    /// The closed escrow.
+    pub escrow: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Wallet the escrow's rent was returned to.
+    pub rent_destination: Pubkey,
+    /// Rent lamports returned.
+    pub rent: u64,
+    /// Wallet that collected or closed the escrow.
+    pub cranker: Pubkey,
+    /// Bounty paid to the cranker (0 once the treasury is empty, or when the cranker
+    /// is the escrow's sender or rent destination).
+    pub bounty: u64,
+}
+
+/// `gc_closed_escrows` as a batch operation.
+struct Collect<'a, 'b, 'c, 'info> {
+    ctx: &'a mut Context<'b, 'c, 'info, 'info, GcClosedEscrows<'info>>,
+    now: i64,
+}
+
+/// A settled escrow ready to be closed.
+struct CollectItem<'info> {
+    escrow: Account<'info, Escrow>,
+    rent_destination: &'info AccountInfo<'info>,
+}
+
+impl<'info> BatchOperation<'info> for Collect<'_, '_, '_, 'info> {
+    type Item = CollectItem<'info>;
+
+    const ACCOUNTS_PER_ITEM: usize = GC_ACCOUNTS_PER_ESCROW;
+
+    fn prepare(&mut self, accounts: &'info [AccountInfo<'info>]) -> Result<Self::Item> {
+        let escrow = Account::<Escrow>::try_from(&accounts[0])?;
+        let rent_destination = &accounts[1];
+
+        // Verify the escrow was claimed or refunded.
+        require!(
+            escrow.status == EscrowStatus::Completed || escrow.status == EscrowStatus::Refunded,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the retention window has passed.
+        let collectable_at = escrow
+            .settled_at
+            .checked_add(ESCROW_RETENTION_SECONDS)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(
+            self.now >= collectable_at,
+            EscrowError::RetentionNotElapsed
+        );
+
+        // Verify the rent goes back to whoever funded the escrow.
+        require_keys_eq!(
+            rent_destination.key(),
+            escrow.refund_recipient(),
+            EscrowError::InvalidBeneficiary
+        );
+
+        Ok(CollectItem {
+            escrow,
+            rent_destination,
+        })
+    }
+
+    fn apply(&mut self, item: Self::Item) -> Result<u64> {
+        let CollectItem {
+            escrow,
+            rent_destination,
+        } = item;
+        let ctx = &mut *self.ctx;
+
+        // An empty treasury does not block cleanup; the escrow is closed without a bounty.
+        // Nor is a bounty paid for collecting one's own escrow.
+        let cranker = ctx.accounts.cranker.key();
+        let own_escrow = cranker == escrow.sender || cranker == rent_destination.key();
+        let treasury = &mut ctx.accounts.treasury;
+        let bounty = if !own_escrow && treasury.available()? >= GC_BOUNTY_LAMPORTS {
+            GC_BOUNTY_LAMPORTS
+        } else {
+            0
+        };
+        treasury.total_paid = treasury
+            .total_paid
+            .checked_add(bounty)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        treasury.collected_count = treasury
+            .collected_count
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        transfer_lamports(
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.cranker.to_account_info(),
+            bounty,
+        )?;
+
+        // Close the escrow, returning its rent.
+        let rent = escrow.to_account_info().lamports();
+        close_account(&escrow.to_account_info(), rent_destination)?;
+
+        emit_cpi!(EscrowCollected {
+            escrow: escrow.key(),
+            thread_id: escrow.thread_id,
+            rent_destination: rent_destination.key(),
+            rent,
+            cranker,
+            bounty,
+        });
+
+        Ok(bounty)
+    }
+
+    fn report_failure(&mut self, failure: BatchItemFailed) -> Result<()> {
+        let ctx = &*self.ctx;
+        emit_cpi!(failure);
+
+        Ok(())
+    }
+}
+
+pub fn process_initialize_gc_treasury(ctx: Context<InitializeGcTreasury>) -> Result<()> {
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+
+    Ok(())
+}
+
+pub fn process_fund_gc_treasury(ctx: Context<FundGcTreasury>, amount: u64) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+
+    treasury.total_funded = treasury
+        .total_funded
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    // Transfer lamports from the funder to the treasury PDA.
+    let ix = system_instruction::transfer(&ctx.accounts.funder.key(), &treasury.key(), amount);
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.funder.to_account_info(),
+            ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn process_gc_closed_escrows<'info>(
+    mut ctx: Context<'_, '_, 'info, 'info, GcClosedEscrows<'info>>,
+) -> Result<()> {
+    let remaining_accounts = ctx.remaining_accounts;
+    let mut collect = Collect {
+        ctx: &mut ctx,
+        now: Clock::get()?.unix_timestamp,
+    };
+
+    // Escrows that are not yet collectable are skipped rather than failing the sweep.
+    run_batch(&mut collect, remaining_accounts, true)?;
+
+    Ok(())
+}
+
+pub fn process_close_settled_escrow(
+    ctx: Context<CloseSettledEscrow>,
+    _sender_pubkey: Pubkey,
+    thread_id: [u8; 32],
+) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+
+    // Verify the thread_id matches.
+    require!(
+        escrow.thread_id == thread_id,
+        EscrowError::ThreadIdMismatch
+    );
+
+    // Verify the escrow was claimed or refunded.
+    require!(
+        escrow.status == EscrowStatus::Completed || escrow.status == EscrowStatus::Refunded,
+        EscrowError::InvalidStatus
+    );
+
+    // Verify the signer is the sender or the wallet the rent returns to.
+    let authority = ctx.accounts.authority.key();
+    require!(
+        authority == escrow.sender || authority == escrow.refund_recipient(),
+        EscrowError::Unauthorized
+    );
+
+    // Verify the rent goes back to whoever funded the escrow.
+    require_keys_eq!(
+        ctx.accounts.rent_destination.key(),
+        escrow.refund_recipient(),
+        EscrowError::InvalidBeneficiary
+    );
+
+    // The account is closed to the rent destination by the `close` constraint.
+    emit_cpi!(EscrowCollected {
+        escrow: escrow.key(),
+        thread_id,
+        rent_destination: ctx.accounts.rent_destination.key(),
+        rent: escrow.to_account_info().lamports(),
+        cranker: authority,
+        bounty: 0,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,318 @@
+//! Group-thread escrows split across every participant who replied.
+//!
+//! Instead of paying a single recipient, a group escrow accumulates platform
+//! attested replies from thread participants before its deadline and, at
+//! settlement, splits the pot among them in proportion to their attested weight.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::{expiry, transfer_lamports, EscrowError, PlatformConfig};
+
+/// Maximum number of distinct participants a group escrow can pay.
+pub const MAX_GROUP_PARTICIPANTS: usize = 16;
+
+/// Escrow for a group thread, paid out pro rata to attested repliers.
+#[account]
+#[derive(Debug)]
+pub struct GroupEscrow {
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Amount of lamports escrowed.
+    pub amount: u64,
+    /// Unix timestamp after which replies no longer count and the pot can be settled.
+    pub deadline: i64,
+    /// Participants who replied, with their attested weights.
+    pub participants: Vec<GroupParticipant>,
+    /// Sum of all participant weights.
+    pub total_weight: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl GroupEscrow {
+    /// Size of the GroupEscrow account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // sender
+        32 + // thread_id
+        8 + // amount
+        8 + // deadline
+        4 + MAX_GROUP_PARTICIPANTS * GroupParticipant::LEN + // participants
+        8 + // total_weight
+        1; // bump
+}
+
+/// A participant who replied to a group thread.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct GroupParticipant {
+    /// Wallet receiving the participant's share.
+    pub wallet: Pubkey,
+    /// Attested reply weight (e.g. number of qualifying replies).
+    pub weight: u32,
+}
+
+impl GroupParticipant {
+    pub const LEN: usize = 32 + 4;
+}
+
+/// Accounts required to create a group escrow.
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct CreateGroupEscrow<'info> {
+    /// The sender funding the escrow.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + GroupEscrow::LEN,
+        seeds = [b"group_escrow", sender.key().as_ref(), &thread_id],
+        bump,
+    )]
+    pub group_escrow: Account<'info, GroupEscrow>,
+
+    /// System program for creating the account and transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required for the platform to record an attested reply.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordGroupReply<'info> {
+    /// Platform attestor vouching for the reply.
+    pub attestor: Signer<'info>,
+
+    /// Platform config naming the attestor.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.attestor == attestor.key() @ EscrowError::InvalidAttestation,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// The group escrow the reply counts towards.
+    #[account(mut)]
+    pub group_escrow: Account<'info, GroupEscrow>,
+}
+
+/// Accounts required to settle a group escrow.
+///
+/// The participant wallets must follow as writable remaining accounts, in the
+/// order they appear in `group_escrow.participants`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleGroupEscrow<'info> {
+    /// Anyone may settle once the deadline has passed.
+    pub cranker: Signer<'info>,
+
+    /// The sender, receiving rounding dust, skipped shares, rent, or the whole pot if
+    /// nobody replied.
+    #[account(mut)]
+    pub sender: SystemAccount<'info>,
+
+    /// The group escrow, closed to the sender once settled.
+    #[account(
+        mut,
+        has_one = sender,
+        close = sender,
+    )]
+    pub group_escrow: Account<'info, GroupEscrow>,
+}
+
+/// Emitted when an attested reply is recorded.
+#[event]
+pub struct GroupReplyRecorded {
+    /// Group escrow the reply counts towards.
+    pub group_escrow: Pubkey,
+    /// Participant whose reply was recorded.
+    pub participant: Pubkey,
+    /// Weight attested for this reply.
+    pub weight: u32,
+    /// Sum of all participant weights after the reply.
+    pub total_weight: u64,
+}
+
+/// Emitted when a group escrow is split among its participants.
+#[event]
+pub struct GroupEscrowSettled {
+    /// The settled group escrow.
+    pub group_escrow: Pubkey,
+    /// Wallet that funded the group escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports paid out across all participants; shares too small to fund an empty
+    /// wallet were returned to the sender instead.
+    pub paid_out: u64,
+    /// Number of participants who replied.
+    pub participant_count: u8,
+}
+
+pub fn process_create_group_escrow(
+    ctx: Context<CreateGroupEscrow>,
+    thread_id: [u8; 32],
+    amount: u64,
+    ttl_seconds: i64,
+) -> Result<()> {
+    let group_escrow = &mut ctx.accounts.group_escrow;
+    let clock = Clock::get()?;
+
+    // Verify the amount is within the protocol limits.
+    ctx.accounts.config.check_escrow_amount(amount)?;
+
+    group_escrow.sender = ctx.accounts.sender.key();
+    group_escrow.thread_id = thread_id;
+    group_escrow.amount = amount;
+    group_escrow.deadline = expiry(clock.unix_timestamp, ttl_seconds)?;
+    group_escrow.participants = Vec::new();
+    group_escrow.total_weight = 0;
+    group_escrow.bump = ctx.bumps.group_escrow;
+
+    // Transfer lamports from the sender to the escrow PDA.
+    let ix = system_instruction::transfer(
+        &ctx.accounts.sender.key(),
+        &group_escrow.key(),
+        amount,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.sender.to_account_info(),
+            ctx.accounts.group_escrow.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn process_record_group_reply(
+    ctx: Context<RecordGroupReply>,
+    participant: Pubkey,
+    weight: u32,
+) -> Result<()> {
+    let group_escrow = &mut ctx.accounts.group_escrow;
+    let clock = Clock::get()?;
+
+    // Only replies before the deadline count.
+    require!(
+        clock.unix_timestamp < group_escrow.deadline,
+        EscrowError::DeadlinePassed
+    );
+
+    // Verify the reply carries weight, so zero-weight participants cannot fill the group.
+    require!(
+        weight > 0,
+        EscrowError::InvalidReplyWeight
+    );
+
+    // Accumulate weight for returning participants, otherwise add a new one.
+    match group_escrow
+        .participants
+        .iter_mut()
+        .find(|existing| existing.wallet == participant)
+    {
+        Some(existing) => {
+            existing.weight = existing
+                .weight
+                .checked_add(weight)
+                .ok_or(EscrowError::ArithmeticOverflow)?;
+        }
+        None => {
+            require!(
+                group_escrow.participants.len() < MAX_GROUP_PARTICIPANTS,
+                EscrowError::TooManyParticipants
+            );
+            group_escrow.participants.push(GroupParticipant {
+                wallet: participant,
+                weight,
+            });
+        }
+    }
+    group_escrow.total_weight = group_escrow
+        .total_weight
+        .checked_add(weight as u64)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    emit_cpi!(GroupReplyRecorded {
+        group_escrow: group_escrow.key(),
+        participant,
+        weight,
+        total_weight: group_escrow.total_weight,
+    });
+
+    Ok(())
+}
+
+pub fn process_settle_group_escrow<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SettleGroupEscrow<'info>>,
+) -> Result<()> {
+    let group_escrow = &ctx.accounts.group_escrow;
+    let clock = Clock::get()?;
+
+    // Verify the reply deadline has passed.
+    require!(
+        clock.unix_timestamp >= group_escrow.deadline,
+        EscrowError::NotExpired
+    );
+    require!(
+        ctx.remaining_accounts.len() == group_escrow.participants.len(),
+        EscrowError::ParticipantMismatch
+    );
+
+    // Pay each participant amount * weight / total_weight; the `close` constraint
+    // returns rounding dust, skipped shares and rent (or the whole pot if nobody
+    // replied) to the sender.
+    let escrow_info = group_escrow.to_account_info();
+    let rent = Rent::get()?;
+    let mut paid_out: u64 = 0;
+    for (participant, wallet) in group_escrow
+        .participants
+        .iter()
+        .zip(ctx.remaining_accounts.iter())
+    {
+        require_keys_eq!(
+            wallet.key(),
+            participant.wallet,
+            EscrowError::ParticipantMismatch
+        );
+
+        let share = (group_escrow.amount as u128)
+            .checked_mul(participant.weight as u128)
+            .and_then(|value| value.checked_div(group_escrow.total_weight as u128))
+            .ok_or(EscrowError::ArithmeticOverflow)? as u64;
+
+        // Skip shares that would leave the wallet below the rent-exempt minimum, such as
+        // dust sent to an unfunded wallet; the runtime would fail the whole settlement.
+        let balance = wallet
+            .lamports()
+            .checked_add(share)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        if !rent.is_exempt(balance, wallet.data_len()) {
+            continue;
+        }
+
+        transfer_lamports(&escrow_info, wallet, share)?;
+        paid_out = paid_out
+            .checked_add(share)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+    }
+
+    emit_cpi!(GroupEscrowSettled {
+        group_escrow: group_escrow.key(),
+        sender: group_escrow.sender,
+        thread_id: group_escrow.thread_id,
+        paid_out,
+        participant_count: group_escrow.participants.len() as u8,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,261 @@
+//! Protocol insurance fund.
+//!
+//! The fund pools lamports set aside to compensate users harmed by protocol bugs.
+//! Payouts are gated by the platform admin and each one leaves an `InsurancePayout`
+//! record, so contributions and payouts can be audited on-chain.
+//!
+//! The program charges no fees, so nothing is skimmed into the fund automatically:
+//! it only grows through direct `contribute_insurance` contributions.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::{transfer_lamports, EscrowError, PlatformConfig};
+
+/// Singleton fund holding insurance lamports.
+#[account]
+#[derive(Debug)]
+pub struct InsuranceFund {
+    /// Lamports ever contributed to the fund.
+    pub total_contributed: u64,
+    /// Lamports ever paid out of the fund.
+    pub total_paid_out: u64,
+    /// Number of payouts made.
+    pub payout_count: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl InsuranceFund {
+    /// Size of the InsuranceFund account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        8 + // total_contributed
+        8 + // total_paid_out
+        8 + // payout_count
+        1; // bump
+
+    /// Lamports contributed and not yet paid out.
+    fn available(&self) -> Result<u64> {
+        self.total_contributed
+            .checked_sub(self.total_paid_out)
+            .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+    }
+}
+
+/// Audit record of a single insurance payout.
+#[account]
+#[derive(Debug)]
+pub struct InsurancePayout {
+    /// Platform identifier of the incident claim being compensated.
+    pub claim_id: [u8; 32],
+    /// Wallet compensated.
+    pub recipient: Pubkey,
+    /// Lamports paid.
+    pub amount: u64,
+    /// Admin that approved the payout.
+    pub approved_by: Pubkey,
+    /// Unix timestamp of the payout.
+    pub paid_at: i64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl InsurancePayout {
+    /// Size of the InsurancePayout account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // claim_id
+        32 + // recipient
+        8 + // amount
+        32 + // approved_by
+        8 + // paid_at
+        1; // bump
+}
+
+/// Accounts required to create the insurance fund.
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    /// Pays for the fund account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Singleton PDA holding the insurance lamports.
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InsuranceFund::LEN,
+        seeds = [b"insurance_fund"],
+        bump,
+    )]
+    pub fund: Account<'info, InsuranceFund>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to contribute to the insurance fund.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ContributeInsurance<'info> {
+    /// The wallet contributing lamports.
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    /// The insurance fund.
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump = fund.bump,
+    )]
+    pub fund: Account<'info, InsuranceFund>,
+
+    /// System program for transferring lamports.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for the admin to pay an insurance claim.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(claim_id: [u8; 32])]
+pub struct PayoutClaim<'info> {
+    /// The platform admin approving the payout.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Platform config naming the admin.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ EscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// The insurance fund paying the claim.
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump = fund.bump,
+    )]
+    pub fund: Account<'info, InsuranceFund>,
+
+    /// Audit record of the payout; one per claim id, so a claim cannot be paid twice.
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + InsurancePayout::LEN,
+        seeds = [b"insurance_payout", claim_id.as_ref()],
+        bump,
+    )]
+    pub payout: Account<'info, InsurancePayout>,
+
+    /// The wallet being compensated.
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+
+    /// System program for creating the audit record.
+    pub system_program: Program<'info, System>,
+}
+
+/// Emitted when lamports are contributed to the insurance fund.
+#[event]
+pub struct InsuranceContributed {
+    /// Wallet that contributed.
+    pub contributor: Pubkey,
+    /// Lamports contributed.
+    pub amount: u64,
+    /// Lamports ever contributed to the fund, this contribution included.
+    pub total_contributed: u64,
+}
+
+/// Emitted when an insurance claim is paid.
+#[event]
+pub struct InsurancePaidOut {
+    /// Platform identifier of the incident claim being compensated.
+    pub claim_id: [u8; 32],
+    /// Wallet compensated.
+    pub recipient: Pubkey,
+    /// Lamports paid.
+    pub amount: u64,
+    /// Admin that approved the payout.
+    pub approved_by: Pubkey,
+}
+
+pub fn process_initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+    ctx.accounts.fund.bump = ctx.bumps.fund;
+
+    Ok(())
+}
+
+pub fn process_contribute_insurance(ctx: Context<ContributeInsurance>, amount: u64) -> Result<()> {
+    let fund = &mut ctx.accounts.fund;
+
+    fund.total_contributed = fund
+        .total_contributed
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    // Transfer lamports from the contributor to the fund PDA.
+    let ix = system_instruction::transfer(&ctx.accounts.contributor.key(), &fund.key(), amount);
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.contributor.to_account_info(),
+            ctx.accounts.fund.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    emit_cpi!(InsuranceContributed {
+        contributor: ctx.accounts.contributor.key(),
+        amount,
+        total_contributed: ctx.accounts.fund.total_contributed,
+    });
+
+    Ok(())
+}
+
+pub fn process_payout_claim(
+    ctx: Context<PayoutClaim>,
+    claim_id: [u8; 32],
+    amount: u64,
+) -> Result<()> {
+    let fund = &mut ctx.accounts.fund;
+    let clock = Clock::get()?;
+
+    // Only contributed lamports can be paid out, never the fund's rent.
+    require!(
+        amount <= fund.available()?,
+        EscrowError::InsufficientFunds
+    );
+
+    fund.total_paid_out = fund
+        .total_paid_out
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    fund.payout_count = fund
+        .payout_count
+        .checked_add(1)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    let payout = &mut ctx.accounts.payout;
+    payout.claim_id = claim_id;
+    payout.recipient = ctx.accounts.recipient.key();
+    payout.amount = amount;
+    payout.approved_by = ctx.accounts.admin.key();
+    payout.paid_at = clock.unix_timestamp;
+    payout.bump = ctx.bumps.payout;
+
+    transfer_lamports(
+        &ctx.accounts.fund.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+
+    emit_cpi!(InsurancePaidOut {
+        claim_id,
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        approved_by: ctx.accounts.admin.key(),
+    });
+
+    Ok(())
+}
@@ -1,10 +1,78 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::{self, BuildMemo, Memo};
+use anchor_spl::token::{self, CloseAccount, SyncNative, Token, TransferChecked};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::state::Mint as SplMint;
+use anchor_spl::token_2022::{self, InitializeMint2, MintTo, Token2022};
+use anchor_spl::token_2022_extensions::non_transferable::{
+    non_transferable_mint_initialize, NonTransferableMintInitialize,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use solana_sha256_hasher::hashv;
+
+pub mod auction;
+pub mod batch;
+pub mod campaign;
+pub mod compression;
+pub mod gc;
+pub mod group;
+pub mod insurance;
+pub mod pool;
+pub mod pyth;
+pub mod state;
+pub mod upgrade;
+
+pub use auction::*;
+pub use batch::*;
+pub use campaign::*;
+pub use gc::*;
+pub use group::*;
+pub use insurance::*;
+pub use pool::*;
+pub use state::*;
+
+use compression::{empty_root, escrow_leaf, replace_leaf, EMPTY_LEAF};
+use pyth::{read_sol_usd_price, usd_cents_to_lamports};
+use upgrade::{migrate_escrow, record_upgraded_escrow};
 
 declare_id!("Cx6XKyjVT5oipy3gdko2A7R4oJYc5ENUqgMapBF7zxkb");
 
-/// 15 days in seconds.
-const FIFTEEN_DAYS: i64 = 15 * 24 * 60 * 60;
+/// Shortest reply window an escrow may be created with (1 day in seconds).
+const MIN_TTL_SECONDS: i64 = 24 * 60 * 60;
+
+/// Longest reply window an escrow may be created with (90 days in seconds).
+const MAX_TTL_SECONDS: i64 = 90 * 24 * 60 * 60;
+
+/// Approximate slot duration in milliseconds, used to convert TTLs into slots.
+const SLOT_DURATION_MS: u64 = 400;
+
+/// Longest total lifetime an extended escrow may reach (180 days in seconds).
+const MAX_ESCROW_LIFETIME_SECONDS: i64 = 180 * 24 * 60 * 60;
+
+/// How long a claim started with `begin_claim` holds the escrow before the
+/// sender may cancel it (1 day in seconds).
+const CLAIM_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+/// Layout version of escrows created by this program; see `upgrade_escrow`.
+///
+/// - v1: the baseline layout, without a `version` field (`Escrow::V1_LEN`).
+/// - v2: the current layout.
+const ESCROW_VERSION: u8 = 2;
+
+/// Denominator for amounts expressed in basis points.
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Reply milestones unlocking successive reputation badge levels.
+///
+/// Each entry is `(minimum replies, maximum average response time in seconds)`.
+const BADGE_MILESTONES: [(u64, i64); 3] = [
+    (5, 3 * 24 * 60 * 60),
+    (25, 2 * 24 * 60 * 60),
+    (100, 24 * 60 * 60),
+];
 
 /// The escrow program powering SolMail's incentivized replies.
 #[program]
@@ -13,57 +81,409 @@ pub mod solmail_escrow {
 
     /// Initialize an escrow account for a given email thread.
     ///
+    /// The lamports and rent come from `payer`, which may differ from the signing
+    /// sender (e.g. a company funding an employee's outreach); unclaimed funds are
+    /// then refunded to the payer.
+    ///
+    /// Retrying the create for an escrow that already exists with the same terms
+    /// succeeds without funding it again, so clients can resend safely; any other
+    /// create for the thread fails with `EscrowAlreadyExists`.
+    /// A settled escrow for the thread must be closed (`close_settled_escrow`)
+    /// before the sender can escrow it again.
     /// - `thread_id` is a 32-byte identifier derived from the email thread (e.g. a hash).
     /// - `amount` is the number of lamports the sender wants to escrow.
+    /// - `receiver` is the wallet of the email recipient, the only one allowed to claim,
+    ///   or `Pubkey::default()` for an open bounty claimable by any attested replier.
+    /// - `ttl_seconds` is the reply window before the sender can refund, within protocol limits.
+    /// - `reply_window_seconds` is the full-payout reply window (0 = until expiry); later
+    ///   replies forfeit `late_haircut_bps` of the payout, refunded like unclaimed funds.
+    /// - `ttl_slots`, if non-zero, makes refunds depend on the slot instead of unix time;
+    ///   it must cover roughly the same protocol limits as `ttl_seconds`.
+    /// - `conditions` are the reply conditions the claim must meet, fixed for the
+    ///   escrow's lifetime.
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_escrow(
         ctx: Context<InitializeEscrow>,
         thread_id: [u8; 32],
         amount: u64,
+        receiver: Pubkey,
+        ttl_seconds: i64,
+        reply_window_seconds: i64,
+        late_haircut_bps: u16,
+        ttl_slots: u64,
+        conditions: ClaimConditions,
     ) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
 
+        // A retried create for an existing escrow succeeds if every term matches.
+        if escrow.sender != Pubkey::default() {
+            require!(
+                escrow.amount == amount
+                    && escrow.usd_cents == 0
+                    && escrow.payer == ctx.accounts.payer.key()
+                    && escrow.opened_with(
+                        receiver,
+                        ttl_seconds,
+                        reply_window_seconds,
+                        late_haircut_bps,
+                        ttl_slots,
+                        &conditions,
+                    )?,
+                EscrowError::EscrowAlreadyExists
+            );
+            return Ok(());
+        }
+
+        // Verify the amount is within the protocol limits.
+        ctx.accounts.config.check_escrow_amount(amount)?;
+
         // Populate escrow state.
-        escrow.sender = ctx.accounts.sender.key();
-        escrow.receiver = Pubkey::default(); // will be set when the receiver claims
-        escrow.thread_id = thread_id;
-        escrow.amount = amount;
-        escrow.created_at = clock.unix_timestamp;
-        escrow.expires_at = clock.unix_timestamp + FIFTEEN_DAYS;
-        escrow.status = EscrowStatus::Pending;
-        escrow.bump = ctx.bumps.escrow;
-
-        // Transfer lamports from the sender to the escrow PDA.
-        let ix = system_instruction::transfer(&ctx.accounts.sender.key(), &escrow.key(), amount);
+        escrow.open(
+            ctx.accounts.sender.key(),
+            receiver,
+            thread_id,
+            amount,
+            clock.unix_timestamp,
+            ttl_seconds,
+            ctx.bumps.escrow,
+        )?;
+        escrow.set_reply_terms(reply_window_seconds, late_haircut_bps)?;
+        escrow.set_slot_expiry(clock.slot, ttl_slots)?;
+        escrow.set_claim_conditions(&conditions)?;
+        ctx.accounts
+            .thread
+            .record_escrow(thread_id, amount, ctx.bumps.thread)?;
+        ctx.accounts
+            .inbox
+            .record_escrow(receiver, amount, escrow.expires_at, ctx.bumps.inbox)?;
+        ctx.accounts.global_stats.record_escrow(amount)?;
+
+        // A third-party payer gets the funds back if the escrow goes unclaimed.
+        escrow.payer = ctx.accounts.payer.key();
+
+        // Transfer lamports from the payer to the escrow PDA.
+        let ix = system_instruction::transfer(&ctx.accounts.payer.key(), &escrow.key(), amount);
         anchor_lang::solana_program::program::invoke(
             &ix,
             &[
-                ctx.accounts.sender.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Link the transaction to the thread for explorers and indexers.
+        if let Some(memo_program) = &ctx.accounts.memo_program {
+            log_thread_memo(memo_program, &thread_id)?;
+        }
+
+        emit_cpi!(EscrowInitialized {
+            escrow: ctx.accounts.escrow.key(),
+            sender: ctx.accounts.escrow.sender,
+            receiver: ctx.accounts.escrow.receiver,
+            thread_id,
+            amount: ctx.accounts.escrow.amount,
+            expires_at: ctx.accounts.escrow.expires_at,
+            reply_deadline: ctx.accounts.escrow.reply_deadline,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize an escrow whose bounty is denominated in USD cents.
+    ///
+    /// The lamports deposited are computed from the Pyth SOL/USD feed, so senders
+    /// can promise a dollar amount without tracking SOL volatility. Like
+    /// `initialize_escrow`, a retried create with the same terms is a no-op.
+    /// - `thread_id` is a 32-byte identifier derived from the email thread (e.g. a hash).
+    /// - `usd_cents` is the bounty in US cents.
+    /// - `receiver` is the wallet of the email recipient, the only one allowed to claim,
+    ///   or `Pubkey::default()` for an open bounty claimable by any attested replier.
+    /// - `ttl_seconds` is the reply window before the sender can refund, within protocol limits.
+    /// - `reply_window_seconds` is the full-payout reply window (0 = until expiry); later
+    ///   replies forfeit `late_haircut_bps` of the payout, refunded like unclaimed funds.
+    /// - `ttl_slots`, if non-zero, makes refunds depend on the slot instead of unix time;
+    ///   it must cover roughly the same protocol limits as `ttl_seconds`.
+    /// - `conditions` are the reply conditions the claim must meet, fixed for the
+    ///   escrow's lifetime.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_usd_escrow(
+        ctx: Context<InitializeUsdEscrow>,
+        thread_id: [u8; 32],
+        usd_cents: u64,
+        receiver: Pubkey,
+        ttl_seconds: i64,
+        reply_window_seconds: i64,
+        late_haircut_bps: u16,
+        ttl_slots: u64,
+        conditions: ClaimConditions,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // A retried create for an existing escrow succeeds if every term matches;
+        // the lamport amount may differ as it was priced at the original create.
+        if escrow.sender != Pubkey::default() {
+            require!(
+                escrow.usd_cents == usd_cents
+                    && escrow.payer == ctx.accounts.payer.key()
+                    && escrow.opened_with(
+                        receiver,
+                        ttl_seconds,
+                        reply_window_seconds,
+                        late_haircut_bps,
+                        ttl_slots,
+                        &conditions,
+                    )?,
+                EscrowError::EscrowAlreadyExists
+            );
+            return Ok(());
+        }
+
+        // Price the bounty against a fresh SOL/USD quote.
+        let sol_usd = read_sol_usd_price(&ctx.accounts.price_update, clock.unix_timestamp)?;
+        let amount = usd_cents_to_lamports(usd_cents, &sol_usd)?;
+
+        // Verify the priced amount is within the protocol limits.
+        ctx.accounts.config.check_escrow_amount(amount)?;
+
+        // Populate escrow state.
+        escrow.open(
+            ctx.accounts.sender.key(),
+            receiver,
+            thread_id,
+            amount,
+            clock.unix_timestamp,
+            ttl_seconds,
+            ctx.bumps.escrow,
+        )?;
+        escrow.set_reply_terms(reply_window_seconds, late_haircut_bps)?;
+        escrow.set_slot_expiry(clock.slot, ttl_slots)?;
+        escrow.set_claim_conditions(&conditions)?;
+        ctx.accounts
+            .thread
+            .record_escrow(thread_id, amount, ctx.bumps.thread)?;
+        ctx.accounts
+            .inbox
+            .record_escrow(receiver, amount, escrow.expires_at, ctx.bumps.inbox)?;
+        ctx.accounts.global_stats.record_escrow(amount)?;
+        escrow.usd_cents = usd_cents;
+
+        // A third-party payer gets the funds back if the escrow goes unclaimed.
+        escrow.payer = ctx.accounts.payer.key();
+
+        // Transfer lamports from the payer to the escrow PDA.
+        let ix = system_instruction::transfer(&ctx.accounts.payer.key(), &escrow.key(), amount);
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
                 ctx.accounts.escrow.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
 
+        // Link the transaction to the thread for explorers and indexers.
+        if let Some(memo_program) = &ctx.accounts.memo_program {
+            log_thread_memo(memo_program, &thread_id)?;
+        }
+
+        emit_cpi!(EscrowInitialized {
+            escrow: ctx.accounts.escrow.key(),
+            sender: ctx.accounts.escrow.sender,
+            receiver: ctx.accounts.escrow.receiver,
+            thread_id,
+            amount: ctx.accounts.escrow.amount,
+            expires_at: ctx.accounts.escrow.expires_at,
+            reply_deadline: ctx.accounts.escrow.reply_deadline,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize an escrow funded from the sender's wrapped SOL token account.
+    ///
+    /// The wSOL is moved into a vault owned by the escrow PDA and unwrapped into the
+    /// escrow by closing the vault, so claims and refunds pay out native lamports.
+    /// Like `initialize_escrow`, a retried create with the same terms is a no-op.
+    /// - `thread_id` is a 32-byte identifier derived from the email thread (e.g. a hash).
+    /// - `amount` is the number of lamports (wSOL base units) the sender wants to escrow.
+    /// - `receiver` is the wallet of the email recipient, the only one allowed to claim,
+    ///   or `Pubkey::default()` for an open bounty claimable by any attested replier.
+    /// - `ttl_seconds` is the reply window before the sender can refund, within protocol limits.
+    /// - `reply_window_seconds` is the full-payout reply window (0 = until expiry); later
+    ///   replies forfeit `late_haircut_bps` of the payout, refunded like unclaimed funds.
+    /// - `ttl_slots`, if non-zero, makes refunds depend on the slot instead of unix time;
+    ///   it must cover roughly the same protocol limits as `ttl_seconds`.
+    /// - `conditions` are the reply conditions the claim must meet, fixed for the
+    ///   escrow's lifetime.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_escrow_from_wsol(
+        ctx: Context<InitializeEscrowFromWsol>,
+        thread_id: [u8; 32],
+        amount: u64,
+        receiver: Pubkey,
+        ttl_seconds: i64,
+        reply_window_seconds: i64,
+        late_haircut_bps: u16,
+        ttl_slots: u64,
+        conditions: ClaimConditions,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+        let sender_key = ctx.accounts.sender.key();
+        let seeds: &[&[u8]] = &[b"escrow", sender_key.as_ref(), &thread_id, &[ctx.bumps.escrow]];
+
+        // A retried create for an existing escrow succeeds if every term matches.
+        if escrow.sender != Pubkey::default() {
+            require!(
+                escrow.amount == amount
+                    && escrow.usd_cents == 0
+                    && escrow.payer == ctx.accounts.payer.key()
+                    && escrow.opened_with(
+                        receiver,
+                        ttl_seconds,
+                        reply_window_seconds,
+                        late_haircut_bps,
+                        ttl_slots,
+                        &conditions,
+                    )?,
+                EscrowError::EscrowAlreadyExists
+            );
+
+            // The vault was created again for the retry; close it back to the payer.
+            token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: ctx.accounts.wsol_vault.to_account_info(),
+                    destination: ctx.accounts.payer.to_account_info(),
+                    authority: ctx.accounts.escrow.to_account_info(),
+                },
+                &[seeds],
+            ))?;
+            return Ok(());
+        }
+
+        // Verify the amount is within the protocol limits.
+        ctx.accounts.config.check_escrow_amount(amount)?;
+
+        // Populate escrow state.
+        escrow.open(
+            ctx.accounts.sender.key(),
+            receiver,
+            thread_id,
+            amount,
+            clock.unix_timestamp,
+            ttl_seconds,
+            ctx.bumps.escrow,
+        )?;
+        escrow.set_reply_terms(reply_window_seconds, late_haircut_bps)?;
+        escrow.set_slot_expiry(clock.slot, ttl_slots)?;
+        escrow.set_claim_conditions(&conditions)?;
+        ctx.accounts
+            .thread
+            .record_escrow(thread_id, amount, ctx.bumps.thread)?;
+        ctx.accounts
+            .inbox
+            .record_escrow(receiver, amount, escrow.expires_at, ctx.bumps.inbox)?;
+        ctx.accounts.global_stats.record_escrow(amount)?;
+
+        // The payer funds the rent, which returns to it once the escrow is closed.
+        escrow.payer = ctx.accounts.payer.key();
+
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let vault_rent = ctx.accounts.wsol_vault.to_account_info().lamports();
+
+        // Count any lamports sent straight to the wSOL account as token balance.
+        token::sync_native(CpiContext::new(
+            token_program.clone(),
+            SyncNative {
+                account: ctx.accounts.sender_wsol.to_account_info(),
+            },
+        ))?;
+
+        // Move the wSOL into the escrow-owned vault.
+        token::transfer_checked(
+            CpiContext::new(
+                token_program.clone(),
+                TransferChecked {
+                    from: ctx.accounts.sender_wsol.to_account_info(),
+                    mint: ctx.accounts.native_mint.to_account_info(),
+                    to: ctx.accounts.wsol_vault.to_account_info(),
+                    authority: ctx.accounts.sender.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.native_mint.decimals,
+        )?;
+
+        // Unwrap by closing the vault into the escrow PDA.
+        token::close_account(CpiContext::new_with_signer(
+            token_program,
+            CloseAccount {
+                account: ctx.accounts.wsol_vault.to_account_info(),
+                destination: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        // Closing the vault also released its rent; hand that back to the payer.
+        transfer_lamports(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            vault_rent,
+        )?;
+
+        // Link the transaction to the thread for explorers and indexers.
+        if let Some(memo_program) = &ctx.accounts.memo_program {
+            log_thread_memo(memo_program, &thread_id)?;
+        }
+
+        emit_cpi!(EscrowInitialized {
+            escrow: ctx.accounts.escrow.key(),
+            sender: ctx.accounts.escrow.sender,
+            receiver: ctx.accounts.escrow.receiver,
+            thread_id,
+            amount: ctx.accounts.escrow.amount,
+            expires_at: ctx.accounts.escrow.expires_at,
+            reply_deadline: ctx.accounts.escrow.reply_deadline,
+        });
+
         Ok(())
     }
 
-    /// Register the receiver's wallet and claim the escrowed funds.
+    /// Claim the escrowed funds as the receiver registered at initialization.
     ///
     /// This is called when the receiver replies to the email thread.
+    /// Open bounties are claimed by the first replier co-signed by the platform attestor.
+    /// The claim may be signed by the receiver or by their registered claim delegate;
+    /// either way the funds go to the receiver.
+    /// Escrows with a reply predicate require `submit_reply_preimage` first.
+    /// Expired escrows can only be claimed if the release was started with `begin_claim`.
+    /// The escrow account is kept after the claim for disputes and indexers; its rent
+    /// returns to the payer when it is closed (see `gc`), not to the receiver.
+    /// USD-denominated escrows also require a SOL/USD price update within the
+    /// staleness bound, so claims only settle while the oracle is live.
     /// - `sender_pubkey` is needed to derive the escrow PDA.
     /// - `thread_id` must match the one used in `initialize_escrow`.
+    /// - `reply_proof_hash` is the hash of the reply proof justifying the payout, kept
+    ///   in the claim's `ClaimReceipt` for auditors.
+    /// - `reply_verdict_hash` is the hash of the off-chain verifier's SPF/DMARC verdict
+    ///   on the reply, kept alongside it (all zeroes when not verified).
     pub fn register_and_claim(
         ctx: Context<RegisterAndClaim>,
         sender_pubkey: Pubkey,
         thread_id: [u8; 32],
+        reply_proof_hash: [u8; 32],
+        reply_verdict_hash: [u8; 32],
     ) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
 
-        // Verify the escrow is in Pending status.
-        require!(
-            escrow.status == EscrowStatus::Pending,
-            EscrowError::InvalidStatus
-        );
+        // Verify the escrow can still be claimed.
+        escrow.check_claimable(&clock)?;
 
         // Verify the thread_id matches.
         require!(
@@ -77,11 +497,86 @@ pub mod solmail_escrow {
             EscrowError::SenderMismatch
         );
 
-        // Set the receiver.
-        escrow.receiver = ctx.accounts.receiver.key();
+        // Escrows addressed to an email hash must be bound to a wallet first.
+        require!(
+            escrow.receiver != Pubkey::default() || escrow.recipient_email_hash == [0; 32],
+            EscrowError::RecipientNotBound
+        );
+
+        // Open bounties have no fixed receiver: the first wallet the platform
+        // attests as a genuine replier becomes the receiver.
+        if escrow.receiver == Pubkey::default() {
+            let config = ctx
+                .accounts
+                .config
+                .as_ref()
+                .ok_or(EscrowError::InvalidAttestation)?;
+            let attestor = ctx
+                .accounts
+                .attestor
+                .as_ref()
+                .ok_or(EscrowError::InvalidAttestation)?;
+            require_keys_eq!(
+                attestor.key(),
+                config.attestor,
+                EscrowError::InvalidAttestation
+            );
+            escrow.receiver = ctx.accounts.receiver.key();
+        }
+
+        // Verify the funds go to the receiver the sender bound the escrow to.
+        require!(
+            escrow.receiver == ctx.accounts.receiver.key(),
+            EscrowError::ReceiverMismatch
+        );
+
+        // Verify the claimant is the receiver or the delegate they designated.
+        let claimant = ctx.accounts.claimant.key();
+        if claimant != ctx.accounts.receiver.key() {
+            let claim_delegate = ctx
+                .accounts
+                .claim_delegate
+                .as_ref()
+                .ok_or(EscrowError::UnauthorizedDelegate)?;
+            require_keys_eq!(
+                claimant,
+                claim_delegate.delegate,
+                EscrowError::UnauthorizedDelegate
+            );
+        }
+
+        // Verify the reply satisfied the sender's content predicate, if one was set.
+        require!(
+            escrow.reply_commitment == [0; 32] || escrow.reply_verified,
+            EscrowError::ReplyNotVerified
+        );
+
+        // Verify the reply's quality score was attested, if the sender set a bar.
+        require!(
+            escrow.quality_bar == 0 || escrow.quality_attested,
+            EscrowError::QualityNotAttested
+        );
+
+        // Re-check the oracle staleness bounds for USD-denominated escrows.
+        if escrow.usd_cents > 0 {
+            let price_update = ctx
+                .accounts
+                .price_update
+                .as_ref()
+                .ok_or(EscrowError::InvalidPriceFeed)?;
+            read_sol_usd_price(price_update, clock.unix_timestamp)?;
+        }
+
+        // Record the reply in the receiver's reputation stats.
+        ctx.accounts.recipient_stats.record_reply(
+            ctx.accounts.receiver.key(),
+            escrow,
+            clock.unix_timestamp,
+            ctx.bumps.recipient_stats,
+        )?;
 
         // Mark as completed.
-        escrow.status = EscrowStatus::Completed;
+        escrow.status.transition_to(EscrowStatus::Completed)?;
 
         // Transfer all lamports from escrow PDA to receiver.
         let escrow_lamports = ctx.accounts.escrow.to_account_info().lamports();
@@ -90,20 +585,190 @@ pub mod solmail_escrow {
             .checked_sub(rent_exempt_minimum)
             .ok_or(EscrowError::InsufficientFunds)?;
 
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= transfer_amount;
-        **ctx.accounts.receiver.to_account_info().try_borrow_mut_lamports()? += transfer_amount;
+        // Reimburse the claimant's transaction fees from the sender-funded buffer.
+        let claim_fee_buffer = ctx.accounts.escrow.claim_fee_buffer;
+        if claim_fee_buffer > 0 {
+            transfer_lamports(
+                &ctx.accounts.escrow.to_account_info(),
+                &ctx.accounts.claimant.to_account_info(),
+                claim_fee_buffer,
+            )?;
+        }
+        let transfer_amount = transfer_amount
+            .checked_sub(claim_fee_buffer)
+            .ok_or(EscrowError::InsufficientFunds)?;
+
+        // Late and low-quality replies return part of the base amount to whoever
+        // funded it; an early-reply bonus whose window has passed goes back to the
+        // sender who added it.
+        let refund_share = ctx
+            .accounts
+            .escrow
+            .refund_share(transfer_amount, clock.unix_timestamp)?;
+        if refund_share > 0 {
+            let destination = if ctx.accounts.escrow.refund_recipient() == sender_pubkey {
+                ctx.accounts
+                    .sender
+                    .as_ref()
+                    .ok_or(EscrowError::SenderMismatch)?
+                    .to_account_info()
+            } else {
+                ctx.accounts
+                    .refund_beneficiary
+                    .as_ref()
+                    .ok_or(EscrowError::InvalidBeneficiary)?
+                    .to_account_info()
+            };
+            transfer_lamports(
+                &ctx.accounts.escrow.to_account_info(),
+                &destination,
+                refund_share,
+            )?;
+        }
+        let forfeited_bonus = ctx.accounts.escrow.forfeited_bonus(clock.unix_timestamp);
+        if forfeited_bonus > 0 {
+            let sender = ctx
+                .accounts
+                .sender
+                .as_ref()
+                .ok_or(EscrowError::SenderMismatch)?;
+            transfer_lamports(
+                &ctx.accounts.escrow.to_account_info(),
+                &sender.to_account_info(),
+                forfeited_bonus,
+            )?;
+        }
+        let transfer_amount = transfer_amount
+            .checked_sub(refund_share)
+            .and_then(|amount| amount.checked_sub(forfeited_bonus))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        transfer_lamports(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.receiver.to_account_info(),
+            transfer_amount,
+        )?;
+        let pending = ctx.accounts.escrow.pending_amount()?;
+        ctx.accounts.thread.settle(pending, transfer_amount)?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.global_stats.record_claim(transfer_amount)?;
+
+        // Record which proof justified the payout.
+        let receipt = &mut ctx.accounts.claim_receipt;
+        receipt.escrow = ctx.accounts.escrow.key();
+        receipt.thread_id = thread_id;
+        receipt.claimer = ctx.accounts.claimant.key();
+        receipt.receiver = ctx.accounts.receiver.key();
+        receipt.amount = transfer_amount;
+        receipt.reply_proof_hash = reply_proof_hash;
+        receipt.slot = clock.slot;
+        receipt.bump = ctx.bumps.claim_receipt;
+        receipt.reply_verdict_hash = reply_verdict_hash;
 
-        // Close the escrow account (return rent to receiver).
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? = 0;
-        ctx.accounts.escrow.to_account_info().assign(&system_program::ID);
-        ctx.accounts.escrow.to_account_info().resize(0)?;
+        // Keep the settled escrow and its rent until `gc_closed_escrows` collects it.
+        ctx.accounts.escrow.settled_at = clock.unix_timestamp;
+
+        // Link the transaction to the thread for explorers and indexers.
+        if let Some(memo_program) = &ctx.accounts.memo_program {
+            log_thread_memo(memo_program, &thread_id)?;
+        }
+
+        emit_cpi!(EscrowClaimed {
+            escrow: ctx.accounts.escrow.key(),
+            sender: sender_pubkey,
+            receiver: ctx.accounts.receiver.key(),
+            thread_id,
+            amount: transfer_amount,
+            subject_hash: ctx.accounts.escrow.subject_hash,
+            attachment_hash: ctx.accounts.escrow.attachment_hash,
+        });
 
         Ok(())
     }
 
+    /// Preview how a claim made now would split the escrow, without changing it.
+    ///
+    /// Meant to be simulated: the `ReleaseQuote` is returned through return data,
+    /// so wallets can show the exact payout without duplicating the payout math.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn quote_release(
+        ctx: Context<QuoteRelease>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+    ) -> Result<ReleaseQuote> {
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the escrow can still be claimed.
+        escrow.check_claimable(&clock)?;
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Same split as `register_and_claim`: fee buffer, refund and bonus shares, then the receiver.
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(8 + Escrow::LEN);
+        let gross_amount = escrow
+            .to_account_info()
+            .lamports()
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(EscrowError::InsufficientFunds)?;
+        let payout = gross_amount
+            .checked_sub(escrow.claim_fee_buffer)
+            .ok_or(EscrowError::InsufficientFunds)?;
+        let refund_share = escrow.refund_share(payout, clock.unix_timestamp)?;
+        let forfeited_bonus = escrow.forfeited_bonus(clock.unix_timestamp);
+        let receiver_amount = payout
+            .checked_sub(refund_share)
+            .and_then(|amount| amount.checked_sub(forfeited_bonus))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        Ok(ReleaseQuote {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            gross_amount,
+            claim_fee_buffer: escrow.claim_fee_buffer,
+            refund_share,
+            forfeited_bonus,
+            receiver_amount,
+            quoted_at: clock.unix_timestamp,
+        })
+    }
+
+    /// Release several escrows to their receiver in one transaction.
+    ///
+    /// Signed by the receiver or their claim delegate. Each escrow is validated like
+    /// `register_and_claim` and passed as remaining accounts (escrow, thread, sender,
+    /// refund destination, claim receipt); open bounties still go through `register_and_claim`. Like a
+    /// single claim, each release leaves a `ClaimReceipt`, pays the fee buffer to the
+    /// claimant, and keeps the escrow with its rent until it is closed.
+    /// - `allow_partial` skips escrows that fail validation instead of failing the batch.
+    pub fn release_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseMany<'info>>,
+        allow_partial: bool,
+    ) -> Result<()> {
+        batch::process_release_many(ctx, allow_partial)
+    }
+
+    /// Refund several expired escrows of one sender in one transaction.
+    ///
+    /// Each escrow is validated like `refund_escrow` and passed as remaining accounts
+    /// (escrow, thread, receiver inbox, refund destination), and kept with its rent
+    /// until it is closed.
+    /// - `allow_partial` skips escrows that fail validation instead of failing the batch.
+    pub fn refund_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundMany<'info>>,
+        allow_partial: bool,
+    ) -> Result<()> {
+        batch::process_refund_many(ctx, allow_partial)
+    }
+
     /// Refund the escrowed funds back to the sender.
     ///
-    /// Can only be called by the sender after the 15-day expiry period.
+    /// Can only be called by the sender after the escrow's expiry.
     /// - `thread_id` must match the one used in `initialize_escrow`.
     pub fn refund_escrow(
         ctx: Context<RefundEscrow>,
@@ -112,9 +777,9 @@ pub mod solmail_escrow {
         let escrow = &ctx.accounts.escrow;
         let clock = Clock::get()?;
 
-        // Verify the escrow is in Pending status (not already completed or refunded).
+        // Verify the escrow is pending or marked expired (not claimed, claiming or refunded).
         require!(
-            escrow.status == EscrowStatus::Pending,
+            escrow.status == EscrowStatus::Pending || escrow.status == EscrowStatus::Expired,
             EscrowError::InvalidStatus
         );
 
@@ -130,151 +795,4241 @@ pub mod solmail_escrow {
             EscrowError::SenderMismatch
         );
 
-        // Verify 15 days have passed.
+        // Verify the escrow has expired.
         require!(
-            clock.unix_timestamp >= escrow.expires_at,
+            escrow.is_expired(&clock),
             EscrowError::NotExpired
         );
 
-        // Transfer all lamports from escrow PDA back to sender.
+        // Transfer all lamports from escrow PDA back to the payer, or the beneficiary.
         let escrow_lamports = ctx.accounts.escrow.to_account_info().lamports();
         let rent_exempt_minimum = Rent::get()?.minimum_balance(8 + Escrow::LEN);
         let transfer_amount = escrow_lamports
             .checked_sub(rent_exempt_minimum)
             .ok_or(EscrowError::InsufficientFunds)?;
+        let destination = refund_destination(
+            &ctx.accounts.escrow,
+            ctx.accounts.sender.to_account_info(),
+            ctx.accounts.refund_beneficiary.as_ref(),
+        )?;
 
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= transfer_amount;
-        **ctx.accounts.sender.to_account_info().try_borrow_mut_lamports()? += transfer_amount;
+        transfer_lamports(
+            &ctx.accounts.escrow.to_account_info(),
+            &destination,
+            transfer_amount,
+        )?;
+        let pending = ctx.accounts.escrow.pending_amount()?;
+        ctx.accounts.thread.settle(pending, 0)?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.global_stats.record_refund()?;
 
-        // Mark as refunded (we'll close in a separate step if needed, but for now just mark it).
+        // Mark as refunded; the escrow and its rent are kept until `gc_closed_escrows`
+        // collects it.
         let escrow_mut = &mut ctx.accounts.escrow;
-        escrow_mut.status = EscrowStatus::Refunded;
+        escrow_mut.status.transition_to(EscrowStatus::Refunded)?;
+        escrow_mut.settled_at = clock.unix_timestamp;
 
-        // Close the escrow account (return rent to sender).
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? = 0;
-        ctx.accounts.escrow.to_account_info().assign(&system_program::ID);
-        ctx.accounts.escrow.to_account_info().resize(0)?;
+        emit_cpi!(EscrowRefunded {
+            escrow: ctx.accounts.escrow.key(),
+            sender: ctx.accounts.sender.key(),
+            thread_id,
+            amount: transfer_amount,
+            beneficiary: destination.key(),
+        });
 
         Ok(())
     }
-}
 
-/// Escrow account storing all data needed to manage the incentive.
-#[account]
-pub struct Escrow {
-    /// Wallet that funded the escrow.
-    pub sender: Pubkey,
-    /// Wallet that will eventually receive the funds (set on claim).
-    pub receiver: Pubkey,
-    /// Deterministic identifier for the email thread.
-    pub thread_id: [u8; 32],
-    /// Amount of lamports escrowed.
-    pub amount: u64,
-    /// Unix timestamp when the escrow was created.
-    pub created_at: i64,
-    /// Unix timestamp after which the sender can refund.
-    pub expires_at: i64,
-    /// Current status of the escrow.
-    pub status: EscrowStatus,
-    /// PDA bump.
-    pub bump: u8,
-}
+    /// Register the automation thread (e.g. a Clockwork thread PDA) that will refund
+    /// this escrow once it expires.
+    ///
+    /// After scheduling, only `refund_automation` may call `crank_refund_escrow`.
+    /// Passing `Pubkey::default()` lets any cranker refund again.
+    pub fn schedule_refund(
+        ctx: Context<ScheduleRefund>,
+        thread_id: [u8; 32],
+        refund_automation: Pubkey,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
 
-impl Escrow {
-    /// Size of the Escrow account (excluding the 8-byte Anchor discriminator).
-    pub const LEN: usize =
-        32 + // sender
-        32 + // receiver
-        32 + // thread_id
-        8 + // amount
-        8 + // created_at
-        8 + // expires_at
-        1 + // status
-        1; // bump
-}
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
 
-/// Simple status enum so we can extend behavior later.
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum EscrowStatus {
-    Pending,
-    Completed,
-    Refunded,
-}
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
 
-/// Accounts required to initialize an escrow.
-#[derive(Accounts)]
-#[instruction(thread_id: [u8; 32])]
-pub struct InitializeEscrow<'info> {
-    /// The sender funding the escrow.
-    #[account(mut)]
-    pub sender: Signer<'info>,
+        escrow.refund_automation = refund_automation;
 
-    /// PDA that will hold the escrowed lamports and state.
-    #[account(
-        init,
-        payer = sender,
-        space = 8 + Escrow::LEN,
-        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
-        bump,
-    )]
-    pub escrow: Account<'info, Escrow>,
+        emit_cpi!(RefundScheduled {
+            escrow: escrow.key(),
+            sender: escrow.sender,
+            thread_id,
+            refund_automation,
+        });
 
-    /// System program for creating the account and transferring lamports.
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-/// Accounts required to register receiver and claim escrowed funds.
-#[derive(Accounts)]
-#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
-pub struct RegisterAndClaim<'info> {
-    /// The receiver claiming the funds.
-    #[account(mut)]
-    pub receiver: Signer<'info>,
+    /// Commit the escrow to the subject line and attachment digest it pays for.
+    ///
+    /// Either hash may be all zeroes when absent. Commitments can only be recorded
+    /// once, while the escrow is pending, and are surfaced again when it is claimed.
+    pub fn commit_escrow_metadata(
+        ctx: Context<CommitEscrowMetadata>,
+        thread_id: [u8; 32],
+        subject_hash: [u8; 32],
+        attachment_hash: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
 
-    /// PDA holding the escrowed lamports and state.
-    #[account(
-        mut,
-        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
-        bump = escrow.bump,
-    )]
-    pub escrow: Account<'info, Escrow>,
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
 
-    /// System program for closing the account.
-    pub system_program: Program<'info, System>,
-}
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
 
-/// Accounts required to refund escrowed funds.
-#[derive(Accounts)]
-#[instruction(thread_id: [u8; 32])]
-pub struct RefundEscrow<'info> {
-    /// The sender who funded the escrow (only they can refund).
-    #[account(mut)]
-    pub sender: Signer<'info>,
+        // Verify nothing has been committed yet, so commitments cannot be swapped.
+        require!(
+            escrow.subject_hash == [0; 32] && escrow.attachment_hash == [0; 32],
+            EscrowError::MetadataAlreadyCommitted
+        );
 
-    /// PDA holding the escrowed lamports and state.
-    #[account(
-        mut,
-        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
-        bump = escrow.bump,
-    )]
-    pub escrow: Account<'info, Escrow>,
+        escrow.subject_hash = subject_hash;
+        escrow.attachment_hash = attachment_hash;
 
-    /// System program for closing the account.
-    pub system_program: Program<'info, System>,
-}
+        emit_cpi!(EscrowMetadataCommitted {
+            escrow: escrow.key(),
+            thread_id,
+            subject_hash,
+            attachment_hash,
+        });
 
-/// Custom error codes for the escrow program.
-#[error_code]
-pub enum EscrowError {
-    #[msg("Escrow is not in a valid status for this operation")]
+        Ok(())
+    }
+
+    /// Reveal the predicate preimage from the reply, unlocking the claim.
+    ///
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn submit_reply_preimage(
+        ctx: Context<SubmitReplyPreimage>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+        preimage: Vec<u8>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is pending, or its release was started with `begin_claim`.
+        require!(
+            escrow.status == EscrowStatus::Pending || escrow.status == EscrowStatus::Claiming,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the signer is the receiver the escrow is bound to.
+        require!(
+            escrow.receiver == ctx.accounts.receiver.key(),
+            EscrowError::ReceiverMismatch
+        );
+
+        // Verify the preimage opens the sender's commitment.
+        require!(
+            escrow.reply_commitment != [0; 32]
+                && hashv(&[&preimage]).to_bytes() == escrow.reply_commitment,
+            EscrowError::InvalidReplyPreimage
+        );
+
+        escrow.reply_verified = true;
+
+        emit_cpi!(ReplyPredicateVerified {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            receiver: escrow.receiver,
+            thread_id,
+        });
+
+        Ok(())
+    }
+
+    /// Record the verified quality score of the receiver's reply (platform attestor only).
+    ///
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn attest_reply_quality(
+        ctx: Context<AttestReplyQuality>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+        quality_score: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is pending, or its release was started with `begin_claim`.
+        require!(
+            escrow.status == EscrowStatus::Pending || escrow.status == EscrowStatus::Claiming,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        escrow.quality_score = quality_score;
+        escrow.quality_attested = true;
+
+        emit_cpi!(ReplyQualityAttested {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            quality_score,
+            quality_bar: escrow.quality_bar,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit lamports reimbursing the claimant's transaction fees at claim time.
+    ///
+    /// The buffer is paid to the claim's signer on top of the payout, so brand-new
+    /// recipient wallets (or a relayer claiming for them) need no SOL up front.
+    /// It returns to the sender with the rest of the escrow on refund.
+    pub fn fund_claim_fee(
+        ctx: Context<FundClaimFee>,
+        thread_id: [u8; 32],
+        lamports: u64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        escrow.claim_fee_buffer = escrow
+            .claim_fee_buffer
+            .checked_add(lamports)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        // Transfer the buffer from the sender to the escrow PDA.
+        let ix = system_instruction::transfer(&ctx.accounts.sender.key(), &escrow.key(), lamports);
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.sender.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Add a bonus pool paid on top of the escrow only if the reply arrives within
+    /// `bonus_window_seconds` of creation; otherwise the bonus returns to the sender.
+    ///
+    /// Can be added once per escrow, by the sender, while the window is still open.
+    pub fn add_early_reply_bonus(
+        ctx: Context<AddEarlyReplyBonus>,
+        thread_id: [u8; 32],
+        bonus: u64,
+        bonus_window_seconds: i64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify no bonus exists yet and the window is open and ends before expiry.
+        let bonus_deadline = escrow
+            .created_at
+            .checked_add(bonus_window_seconds)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(
+            escrow.bonus == 0
+                && bonus > 0
+                && clock.unix_timestamp < bonus_deadline
+                && bonus_deadline <= escrow.expires_at,
+            EscrowError::InvalidBonus
+        );
+
+        escrow.bonus = bonus;
+        escrow.bonus_deadline = bonus_deadline;
+        ctx.accounts.thread.add_pending(bonus)?;
+        ctx.accounts.inbox.add_pending(bonus)?;
+        ctx.accounts.global_stats.add_volume(bonus)?;
+
+        // Transfer the bonus from the sender to the escrow PDA.
+        let ix = system_instruction::transfer(&ctx.accounts.sender.key(), &escrow.key(), bonus);
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.sender.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit_cpi!(EarlyReplyBonusAdded {
+            escrow: ctx.accounts.escrow.key(),
+            thread_id,
+            bonus,
+            bonus_deadline,
+        });
+
+        Ok(())
+    }
+
+    /// Route the escrow's funds to a beneficiary (e.g. a charity) if it expires unclaimed.
+    ///
+    /// Passing `Pubkey::default()` refunds the sender again. The choice is public:
+    /// it is stored on the escrow and emitted in `RefundBeneficiarySet`. Escrows
+    /// funded by a third-party payer always refund the payer, so their beneficiary
+    /// cannot be changed.
+    pub fn set_refund_beneficiary(
+        ctx: Context<SetRefundBeneficiary>,
+        thread_id: [u8; 32],
+        refund_beneficiary: Pubkey,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the sender funded the escrow, so a payer's refund is never redirected.
+        require!(
+            escrow.payer == escrow.sender,
+            EscrowError::Unauthorized
+        );
+
+        escrow.refund_beneficiary = refund_beneficiary;
+
+        emit_cpi!(RefundBeneficiarySet {
+            escrow: escrow.key(),
+            sender: escrow.sender,
+            thread_id,
+            refund_beneficiary,
+        });
+
+        Ok(())
+    }
+
+    /// Push the escrow's expiry forward for slow but ongoing conversations.
+    ///
+    /// The total lifetime since creation may not exceed `MAX_ESCROW_LIFETIME_SECONDS`.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    /// - `extension_seconds` is added to the current `expires_at`.
+    pub fn extend_escrow(
+        ctx: Context<ExtendEscrow>,
+        thread_id: [u8; 32],
+        extension_seconds: i64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        escrow.extend(extension_seconds)?;
+        ctx.accounts.inbox.record_expiry(escrow.expires_at);
+
+        emit_cpi!(EscrowExtended {
+            escrow: escrow.key(),
+            thread_id,
+            expires_at: escrow.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Grow an escrow written with an older layout in place to the current one.
+    ///
+    /// Escrows created by an earlier `ESCROW_VERSION` cannot be read with the
+    /// current layout; upgrading maps their fields onto it, filling the new ones
+    /// with the defaults of a fresh escrow, so senders need not refund and recreate.
+    /// Pending escrows are also recorded in their `Thread` and `Inbox`, which the
+    /// baseline did not have, so they can be claimed or refunded. The sender pays
+    /// the extra rent.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn upgrade_escrow(ctx: Context<UpgradeEscrow>, thread_id: [u8; 32]) -> Result<()> {
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+
+        // Decode the old layout before the account is resized.
+        let old_len = escrow_info.data_len();
+        let escrow = migrate_escrow(&escrow_info.try_borrow_data()?)?;
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Count the escrow where the settle paths expect to find it.
+        record_upgraded_escrow(
+            &escrow,
+            &mut ctx.accounts.thread,
+            ctx.bumps.thread,
+            &mut ctx.accounts.inbox,
+            ctx.bumps.inbox,
+        )?;
+
+        // Fund the rent of the larger account, then grow it and write the new layout.
+        let rent = Rent::get()?;
+        let extra_rent = rent
+            .minimum_balance(8 + Escrow::LEN)
+            .checked_sub(rent.minimum_balance(old_len))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        let ix = system_instruction::transfer(
+            &ctx.accounts.sender.key(),
+            &escrow_info.key(),
+            extra_rent,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.sender.to_account_info(),
+                escrow_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        escrow_info.resize(8 + Escrow::LEN)?;
+        escrow.try_serialize(&mut &mut escrow_info.try_borrow_mut_data()?[..])?;
+
+        emit_cpi!(EscrowUpgraded {
+            escrow: escrow_info.key(),
+            thread_id,
+            version: ESCROW_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Refund an expired escrow to its sender on behalf of a keeper.
+    ///
+    /// The cranker is the only signer; the funds go back to the sender, or their
+    /// beneficiary, so unattended keepers can clean up expired escrows. Like
+    /// `refund_escrow`, the escrow and its rent are kept until it is closed.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn crank_refund_escrow(
+        ctx: Context<CrankRefundEscrow>,
+        thread_id: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the escrow is pending or marked expired.
+        require!(
+            escrow.status == EscrowStatus::Pending || escrow.status == EscrowStatus::Expired,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Only the scheduled automation may crank, if one was registered.
+        require!(
+            escrow.refund_automation == Pubkey::default()
+                || escrow.refund_automation == ctx.accounts.cranker.key(),
+            EscrowError::UnauthorizedCranker
+        );
+
+        // Verify the escrow has expired.
+        require!(
+            escrow.is_expired(&clock),
+            EscrowError::NotExpired
+        );
+
+        escrow.status.transition_to(EscrowStatus::Refunded)?;
+        escrow.settled_at = clock.unix_timestamp;
+        let pending = escrow.pending_amount()?;
+        ctx.accounts.thread.settle(pending, 0)?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.global_stats.record_refund()?;
+
+        // Everything but the rent goes to the payer, or the beneficiary.
+        let destination = refund_destination(
+            &ctx.accounts.escrow,
+            ctx.accounts.sender.to_account_info(),
+            ctx.accounts.refund_beneficiary.as_ref(),
+        )?;
+        let escrow_lamports = ctx.accounts.escrow.to_account_info().lamports();
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(8 + Escrow::LEN);
+        let transfer_amount = escrow_lamports
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(EscrowError::InsufficientFunds)?;
+        transfer_lamports(
+            &ctx.accounts.escrow.to_account_info(),
+            &destination,
+            transfer_amount,
+        )?;
+
+        emit_cpi!(EscrowRefunded {
+            escrow: ctx.accounts.escrow.key(),
+            sender: ctx.accounts.escrow.sender,
+            thread_id,
+            amount: transfer_amount,
+            beneficiary: destination.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Create the platform config, naming the attestor that vouches for replies
+    /// and the moderator allowed to cancel abusive escrows.
+    ///
+    /// Only the program's upgrade authority may initialize the config, and becomes its admin.
+    /// - `min_escrow_amount` / `max_escrow_amount` bound the lamports of every new
+    ///   escrow, blocking dust spam and oversized deposits (`max` of 0 = no cap); group
+    ///   escrows, campaign entries, pool contributions and auction bids are bounded alike.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        attestor: Pubkey,
+        moderator: Pubkey,
+        min_escrow_amount: u64,
+        max_escrow_amount: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.admin = ctx.accounts.admin.key();
+        config.attestor = attestor;
+        config.moderator = moderator;
+        config.set_escrow_limits(min_escrow_amount, max_escrow_amount)?;
+        config.bump = ctx.bumps.config;
+
+        Ok(())
+    }
+
+    /// Rotate the platform admin, attestor and moderator, and adjust the escrow amount limits.
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Pubkey,
+        attestor: Pubkey,
+        moderator: Pubkey,
+        min_escrow_amount: u64,
+        max_escrow_amount: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.admin = admin;
+        config.attestor = attestor;
+        config.moderator = moderator;
+        config.set_escrow_limits(min_escrow_amount, max_escrow_amount)?;
+
+        Ok(())
+    }
+
+    /// Refund a pending or disputed escrow flagged as abusive or phishing, before its expiry.
+    ///
+    /// Only the platform moderator may cancel. The reason is kept in an on-chain
+    /// `ModerationRecord` for the escrow so every cancellation can be audited; like
+    /// claim receipts, records are keyed by the escrow's creation time, so an escrow
+    /// re-opened at the same address after being closed gets a record of its own.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    /// - `reason_code` is a platform-defined moderation reason (e.g. spam, phishing).
+    pub fn moderator_cancel(
+        ctx: Context<ModeratorCancel>,
+        thread_id: [u8; 32],
+        reason_code: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the escrow is still open (pending or frozen by a dispute).
+        require!(
+            escrow.status == EscrowStatus::Pending || escrow.status == EscrowStatus::Disputed,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        escrow.status.transition_to(EscrowStatus::Refunded)?;
+        escrow.settled_at = clock.unix_timestamp;
+        let pending = escrow.pending_amount()?;
+        ctx.accounts.thread.settle(pending, 0)?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.global_stats.record_refund()?;
+
+        // Everything but the rent goes back to the sender, or their payer or
+        // beneficiary; the escrow is kept until it is closed, like every other
+        // settled escrow.
+        let destination = refund_destination(
+            escrow,
+            ctx.accounts.sender.to_account_info(),
+            ctx.accounts.refund_beneficiary.as_ref(),
+        )?;
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(8 + Escrow::LEN);
+        let transfer_amount = escrow
+            .to_account_info()
+            .lamports()
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(EscrowError::InsufficientFunds)?;
+        transfer_lamports(&escrow.to_account_info(), &destination, transfer_amount)?;
+
+        let record = &mut ctx.accounts.moderation_record;
+        record.escrow = escrow.key();
+        record.moderator = ctx.accounts.moderator.key();
+        record.thread_id = thread_id;
+        record.reason_code = reason_code;
+        record.cancelled_at = clock.unix_timestamp;
+        record.bump = ctx.bumps.moderation_record;
+
+        emit_cpi!(EscrowModerated {
+            escrow: escrow.key(),
+            sender: escrow.sender,
+            moderator: record.moderator,
+            thread_id,
+            amount: transfer_amount,
+            reason_code,
+        });
+
+        Ok(())
+    }
+
+    /// Freeze a pending escrow while the platform moderator reviews a dispute.
+    ///
+    /// Either the sender or the receiver may open a dispute; claims and refunds
+    /// are blocked until `resolve_dispute` or `moderator_cancel`.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    /// - `reason_code` is a platform-defined dispute reason.
+    pub fn open_dispute(
+        ctx: Context<OpenDispute>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+        reason_code: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the disputant is a party to the escrow.
+        let disputant = ctx.accounts.disputant.key();
+        require!(
+            disputant == escrow.sender || disputant == escrow.receiver,
+            EscrowError::Unauthorized
+        );
+
+        escrow.status.transition_to(EscrowStatus::Disputed)?;
+        escrow.disputed_at = clock.unix_timestamp;
+        escrow.dispute_reason = reason_code;
+
+        emit_cpi!(DisputeOpened {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            disputant,
+            reason_code,
+        });
+
+        Ok(())
+    }
+
+    /// Dismiss a dispute, returning the escrow to Pending so it can be claimed or refunded.
+    ///
+    /// Only the platform moderator may resolve; upheld disputes use `moderator_cancel`.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn resolve_dispute(
+        ctx: Context<ResolveDispute>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is frozen by a dispute.
+        require!(
+            escrow.status == EscrowStatus::Disputed,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        escrow.status.transition_to(EscrowStatus::Pending)?;
+        escrow.claim_started_at = 0;
+
+        emit_cpi!(DisputeResolved {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            moderator: ctx.accounts.moderator.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Mark an expired escrow as Expired, closing it to claims ahead of its refund.
+    ///
+    /// Permissionless, so the platform or anyone else can settle the race between
+    /// a late claim and the refund; releases started with `begin_claim` are left alone.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn expire_escrow(
+        ctx: Context<ExpireEscrow>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the escrow has expired.
+        require!(escrow.is_expired(&clock), EscrowError::NotExpired);
+
+        escrow.status.transition_to(EscrowStatus::Expired)?;
+
+        emit_cpi!(EscrowExpired {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            expires_at: escrow.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Start a two-phase release as the bound receiver, moving the escrow to Claiming.
+    ///
+    /// A started release is no longer refundable once the escrow expires, so the
+    /// receiver can gather a reply verification or quality attestation before
+    /// completing it with `register_and_claim` or `release_many`. The sender may
+    /// cancel it with `cancel_stale_claim` after `CLAIM_WINDOW_SECONDS`.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn begin_claim(
+        ctx: Context<BeginClaim>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the signer is the receiver the escrow is bound to.
+        require!(
+            escrow.receiver != Pubkey::default()
+                && escrow.receiver == ctx.accounts.receiver.key(),
+            EscrowError::ReceiverMismatch
+        );
+
+        // Verify the escrow has not expired yet.
+        require!(!escrow.is_expired(&clock), EscrowError::AlreadyExpired);
+
+        escrow.status.transition_to(EscrowStatus::Claiming)?;
+        escrow.claim_started_at = clock.unix_timestamp;
+
+        emit_cpi!(ClaimStarted {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            receiver: escrow.receiver,
+            started_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a release the receiver started but did not complete within
+    /// `CLAIM_WINDOW_SECONDS`, returning the escrow to Pending.
+    ///
+    /// Only the sender may cancel; an expired escrow then becomes refundable again.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn cancel_stale_claim(ctx: Context<CancelStaleClaim>, thread_id: [u8; 32]) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify a release was started.
+        require!(
+            escrow.status == EscrowStatus::Claiming,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the claim window has elapsed.
+        let stale_at = escrow
+            .claim_started_at
+            .checked_add(CLAIM_WINDOW_SECONDS)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(
+            clock.unix_timestamp >= stale_at,
+            EscrowError::ClaimNotStale
+        );
+
+        escrow.status.transition_to(EscrowStatus::Pending)?;
+        escrow.claim_started_at = 0;
+
+        emit_cpi!(ClaimCancelled {
+            escrow: escrow.key(),
+            sender: escrow.sender,
+            thread_id,
+            receiver: escrow.receiver,
+        });
+
+        Ok(())
+    }
+
+    /// Create the singleton account tracking program-wide escrow totals.
+    ///
+    /// Anyone may pay to create it once; escrow instructions require it afterwards.
+    pub fn initialize_global_stats(ctx: Context<InitializeGlobalStats>) -> Result<()> {
+        ctx.accounts.global_stats.bump = ctx.bumps.global_stats;
+
+        Ok(())
+    }
+
+    /// Create the program-wide soulbound badge mint.
+    ///
+    /// The mint is a Token-2022 mint with the `NonTransferable` extension, so badges
+    /// can never leave the wallet they were minted to. Anyone may pay to create it once.
+    pub fn initialize_badge_mint(ctx: Context<InitializeBadgeMint>) -> Result<()> {
+        let badge_mint = &ctx.accounts.badge_mint;
+        let token_program = &ctx.accounts.token_program;
+        let seeds: &[&[u8]] = &[b"badge_mint", &[ctx.bumps.badge_mint]];
+
+        // Allocate the mint account with room for the NonTransferable extension.
+        let space =
+            ExtensionType::try_calculate_account_len::<SplMint>(&[ExtensionType::NonTransferable])?;
+        let lamports = Rent::get()?.minimum_balance(space);
+        let ix = system_instruction::create_account(
+            &ctx.accounts.payer.key(),
+            &badge_mint.key(),
+            lamports,
+            space as u64,
+            &token_program.key(),
+        );
+        anchor_lang::solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                badge_mint.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+
+        // The extension must be initialized before the mint itself.
+        non_transferable_mint_initialize(CpiContext::new(
+            token_program.to_account_info(),
+            NonTransferableMintInitialize {
+                token_program_id: token_program.to_account_info(),
+                mint: badge_mint.to_account_info(),
+            },
+        ))?;
+
+        // The mint PDA is its own mint authority; badges are whole units.
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                token_program.to_account_info(),
+                InitializeMint2 {
+                    mint: badge_mint.to_account_info(),
+                },
+            ),
+            0,
+            &badge_mint.key(),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Mint reputation badges for every milestone the recipient has newly reached.
+    ///
+    /// The recipient's badge balance equals the highest milestone level reached,
+    /// which clients can display as a trust signal.
+    pub fn claim_reputation_badge(ctx: Context<ClaimReputationBadge>) -> Result<()> {
+        let stats = &mut ctx.accounts.recipient_stats;
+
+        // Find the highest milestone satisfied by the recipient's stats.
+        let reached = BADGE_MILESTONES
+            .iter()
+            .take_while(|(min_replies, max_avg_response)| {
+                stats.reply_count >= *min_replies
+                    && stats.total_response_time / stats.reply_count as i64 <= *max_avg_response
+            })
+            .count() as u8;
+
+        require!(
+            reached > stats.badge_level,
+            EscrowError::NoNewBadgeMilestone
+        );
+
+        let new_badges = (reached - stats.badge_level) as u64;
+        stats.badge_level = reached;
+
+        // Mint one badge per newly reached level, signed by the mint PDA.
+        let seeds: &[&[u8]] = &[b"badge_mint", &[ctx.bumps.badge_mint]];
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.badge_mint.to_account_info(),
+                    to: ctx.accounts.recipient_badge_account.to_account_info(),
+                    authority: ctx.accounts.badge_mint.to_account_info(),
+                },
+                &[seeds],
+            ),
+            new_badges,
+        )?;
+
+        Ok(())
+    }
+
+    /// Create a campaign holding many per-recipient escrows in one zero-copy account.
+    ///
+    /// - `ttl_seconds` is the reply window before the sender can refund, within protocol limits.
+    pub fn initialize_campaign(ctx: Context<InitializeCampaign>, ttl_seconds: i64) -> Result<()> {
+        campaign::process_initialize_campaign(ctx, ttl_seconds)
+    }
+
+    /// Add a batch of recipient entries to a campaign and deposit their lamports.
+    pub fn add_campaign_entries(
+        ctx: Context<AddCampaignEntries>,
+        entries: Vec<CampaignEntryInput>,
+    ) -> Result<()> {
+        campaign::process_add_campaign_entries(ctx, entries)
+    }
+
+    /// Release a batch of campaign entries addressed to the signing recipient.
+    ///
+    /// - `indices` are the positions of the recipient's entries in the campaign.
+    pub fn release_campaign_entries(
+        ctx: Context<ReleaseCampaignEntries>,
+        indices: Vec<u16>,
+    ) -> Result<()> {
+        campaign::process_release_campaign_entries(ctx, indices)
+    }
+
+    /// Refund every unreleased entry of an expired campaign and close it.
+    pub fn refund_campaign(ctx: Context<RefundCampaign>) -> Result<()> {
+        campaign::process_refund_campaign(ctx)
+    }
+
+    /// Open an auction for a day of priority inbox placement.
+    ///
+    /// - `day` is the day index (unix timestamp / 86400) the slots apply to.
+    /// - `slots` is the number of priority slots on offer.
+    /// - `closes_at` is the unix timestamp after which no bids are accepted.
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        day: u64,
+        slots: u8,
+        closes_at: i64,
+    ) -> Result<()> {
+        auction::process_create_auction(ctx, day, slots, closes_at)
+    }
+
+    /// Bid escrowed lamports for a priority slot in the recipient's inbox.
+    pub fn place_bid(ctx: Context<PlaceBid>, thread_id: [u8; 32], amount: u64) -> Result<()> {
+        auction::process_place_bid(ctx, thread_id, amount)
+    }
+
+    /// Claim a winning bid as the recipient after replying to its thread.
+    pub fn claim_winning_bid(ctx: Context<ClaimWinningBid>) -> Result<()> {
+        auction::process_claim_winning_bid(ctx)
+    }
+
+    /// Refund a losing bid after close, or an unclaimed winner after the reply window.
+    ///
+    /// Callable by anyone so keepers can refund bids automatically.
+    pub fn close_bid(ctx: Context<CloseBid>) -> Result<()> {
+        auction::process_close_bid(ctx)
+    }
+
+    /// Address a receiver-less escrow to the hash of the recipient's email address.
+    ///
+    /// For recipients without a known wallet: the escrow is created with
+    /// `receiver = Pubkey::default()` and becomes claimable once `bind_recipient`
+    /// attests which wallet owns the email.
+    pub fn set_recipient_email_hash(
+        ctx: Context<SetRecipientEmailHash>,
+        thread_id: [u8; 32],
+        recipient_email_hash: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Only escrows not yet bound to a wallet can be addressed by email.
+        require!(
+            escrow.receiver == Pubkey::default(),
+            EscrowError::ReceiverMismatch
+        );
+
+        escrow.recipient_email_hash = recipient_email_hash;
+
+        Ok(())
+    }
+
+    /// Bind an email-addressed escrow to the wallet that owns the email.
+    ///
+    /// Signed by the platform attestor, which verifies ownership of the email
+    /// (e.g. through a DKIM proof) off-chain. Normal claiming proceeds afterwards.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn bind_recipient(
+        ctx: Context<BindRecipient>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+        receiver: Pubkey,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the escrow is addressed to an email and not bound yet.
+        require!(
+            escrow.receiver == Pubkey::default()
+                && escrow.recipient_email_hash != [0; 32]
+                && receiver != Pubkey::default(),
+            EscrowError::ReceiverMismatch
+        );
+
+        // Move the escrow from the unbound inbox to the receiver's.
+        let pending = escrow.pending_amount()?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.new_inbox.record_escrow(
+            receiver,
+            pending,
+            escrow.expires_at,
+            ctx.bumps.new_inbox,
+        )?;
+        escrow.receiver = receiver;
+
+        emit_cpi!(RecipientBound {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            recipient_email_hash: escrow.recipient_email_hash,
+            receiver,
+        });
+
+        Ok(())
+    }
+
+    /// Transfer claim rights to a new handler after the thread was forwarded.
+    ///
+    /// Signed either by the current receiver or by the platform attestor, which
+    /// vouches that the thread now belongs to `new_receiver`.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn reassign_recipient(
+        ctx: Context<ReassignRecipient>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+        new_receiver: Pubkey,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let authority = ctx.accounts.authority.key();
+
+        // Verify the escrow is in Pending status.
+        require!(
+            escrow.status == EscrowStatus::Pending,
+            EscrowError::InvalidStatus
+        );
+
+        // Verify the thread_id matches.
+        require!(
+            escrow.thread_id == thread_id,
+            EscrowError::ThreadIdMismatch
+        );
+
+        // Verify the signer is the current receiver or the platform attestor.
+        let is_attestor = ctx
+            .accounts
+            .config
+            .as_ref()
+            .is_some_and(|config| config.attestor == authority);
+        require!(
+            authority == escrow.receiver || is_attestor,
+            EscrowError::Unauthorized
+        );
+
+        // Verify the escrow stays bound to a wallet; it cannot become an open bounty.
+        require!(
+            new_receiver != Pubkey::default(),
+            EscrowError::RecipientNotBound
+        );
+
+        // Move the escrow from the previous receiver's inbox to the new one.
+        require!(
+            new_receiver != escrow.receiver,
+            EscrowError::ReceiverMismatch
+        );
+        let pending = escrow.pending_amount()?;
+        ctx.accounts.inbox.settle(pending)?;
+        ctx.accounts.new_inbox.record_escrow(
+            new_receiver,
+            pending,
+            escrow.expires_at,
+            ctx.bumps.new_inbox,
+        )?;
+
+        // The new handler has to satisfy any reply predicate and quality bar themselves.
+        let previous_receiver = escrow.receiver;
+        escrow.receiver = new_receiver;
+        escrow.reply_verified = false;
+        escrow.quality_attested = false;
+        escrow.quality_score = 0;
+
+        emit_cpi!(RecipientReassigned {
+            escrow: escrow.key(),
+            sender: sender_pubkey,
+            thread_id,
+            previous_receiver,
+            new_receiver,
+            authority,
+        });
+
+        Ok(())
+    }
+
+    /// Designate a wallet allowed to claim escrows on the recipient's behalf.
+    ///
+    /// Claimed funds still flow to the recipient; pass `Pubkey::default()` to revoke.
+    pub fn set_claim_delegate(ctx: Context<SetClaimDelegate>, delegate: Pubkey) -> Result<()> {
+        let claim_delegate = &mut ctx.accounts.claim_delegate;
+
+        claim_delegate.recipient = ctx.accounts.recipient.key();
+        claim_delegate.delegate = delegate;
+        claim_delegate.bump = ctx.bumps.claim_delegate;
+
+        emit_cpi!(ClaimDelegateSet {
+            recipient: claim_delegate.recipient,
+            delegate,
+        });
+
+        Ok(())
+    }
+
+    /// Escrow lamports for a group thread, split among everyone who replies.
+    pub fn create_group_escrow(
+        ctx: Context<CreateGroupEscrow>,
+        thread_id: [u8; 32],
+        amount: u64,
+        ttl_seconds: i64,
+    ) -> Result<()> {
+        group::process_create_group_escrow(ctx, thread_id, amount, ttl_seconds)
+    }
+
+    /// Record a participant's attested reply to a group thread (platform attestor only).
+    pub fn record_group_reply(
+        ctx: Context<RecordGroupReply>,
+        participant: Pubkey,
+        weight: u32,
+    ) -> Result<()> {
+        group::process_record_group_reply(ctx, participant, weight)
+    }
+
+    /// Split a group escrow pro rata among its repliers once the deadline passes.
+    ///
+    /// Callable by anyone; participant wallets are passed as remaining accounts.
+    /// Shares too small to leave a wallet rent-exempt go back to the sender.
+    pub fn settle_group_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleGroupEscrow<'info>>,
+    ) -> Result<()> {
+        group::process_settle_group_escrow(ctx)
+    }
+
+    /// Open a bounty for a thread that several senders can fund together.
+    pub fn create_bounty_pool(
+        ctx: Context<CreateBountyPool>,
+        thread_id: [u8; 32],
+        receiver: Pubkey,
+        ttl_seconds: i64,
+    ) -> Result<()> {
+        pool::process_create_bounty_pool(ctx, thread_id, receiver, ttl_seconds)
+    }
+
+    /// Add lamports to a bounty pool, tracking the contributor's share.
+    pub fn contribute_to_pool(ctx: Context<ContributeToPool>, amount: u64) -> Result<()> {
+        pool::process_contribute_to_pool(ctx, amount)
+    }
+
+    /// Claim everything pooled as the bounty's receiver.
+    pub fn claim_bounty_pool(ctx: Context<ClaimBountyPool>) -> Result<()> {
+        pool::process_claim_bounty_pool(ctx)
+    }
+
+    /// Close a contribution, refunding its share if the pool expired unclaimed.
+    ///
+    /// Callable by anyone so keepers can refund contributors automatically. Settling
+    /// the last contribution also closes the pool, returning its rent to the creator.
+    pub fn settle_pool_contribution(ctx: Context<SettlePoolContribution>) -> Result<()> {
+        pool::process_settle_pool_contribution(ctx)
+    }
+
+    /// Create the protocol insurance fund. Anyone may pay to create it once.
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        insurance::process_initialize_insurance_fund(ctx)
+    }
+
+    /// Contribute lamports to the protocol insurance fund.
+    pub fn contribute_insurance(ctx: Context<ContributeInsurance>, amount: u64) -> Result<()> {
+        insurance::process_contribute_insurance(ctx, amount)
+    }
+
+    /// Compensate a user harmed by a protocol bug (platform admin only).
+    ///
+    /// - `claim_id` identifies the incident claim; each can be paid at most once.
+    pub fn payout_claim(ctx: Context<PayoutClaim>, claim_id: [u8; 32], amount: u64) -> Result<()> {
+        insurance::process_payout_claim(ctx, claim_id, amount)
+    }
+
+    /// Create the treasury paying garbage collection bounties. Anyone may pay to create it once.
+    pub fn initialize_gc_treasury(ctx: Context<InitializeGcTreasury>) -> Result<()> {
+        gc::process_initialize_gc_treasury(ctx)
+    }
+
+    /// Fund the garbage collection bounty treasury.
+    pub fn fund_gc_treasury(ctx: Context<FundGcTreasury>, amount: u64) -> Result<()> {
+        gc::process_fund_gc_treasury(ctx, amount)
+    }
+
+    /// Close claimed or refunded escrows past their retention window.
+    ///
+    /// Callable by anyone, who earns a bounty per escrow closed while the treasury
+    /// can pay it, except for escrows they sent or whose rent they receive; escrows are passed as remaining accounts (escrow, rent destination)
+    /// and ones not yet collectable are skipped.
+    pub fn gc_closed_escrows<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GcClosedEscrows<'info>>,
+    ) -> Result<()> {
+        gc::process_gc_closed_escrows(ctx)
+    }
+
+    /// Close a claimed or refunded escrow without waiting for its retention window.
+    ///
+    /// Signed by the sender or the wallet the rent returns to (the refund beneficiary,
+    /// or the payer); no bounty is paid. Closing also frees the escrow address, so
+    /// the sender can escrow the thread again.
+    /// - `sender_pubkey` is needed to derive the escrow PDA.
+    /// - `thread_id` must match the one used in `initialize_escrow`.
+    pub fn close_settled_escrow(
+        ctx: Context<CloseSettledEscrow>,
+        sender_pubkey: Pubkey,
+        thread_id: [u8; 32],
+    ) -> Result<()> {
+        gc::process_close_settled_escrow(ctx, sender_pubkey, thread_id)
+    }
+
+    /// Create the sender's compressed escrow tree.
+    ///
+    /// The tree account is paid for once; every compressed escrow appended to it
+    /// afterwards costs no rent, which keeps sub-0.01 SOL incentives viable.
+    pub fn initialize_escrow_tree(ctx: Context<InitializeEscrowTree>) -> Result<()> {
+        let tree = &mut ctx.accounts.tree;
+
+        tree.sender = ctx.accounts.sender.key();
+        tree.root = empty_root();
+        tree.next_index = 0;
+        tree.bump = ctx.bumps.tree;
+
+        Ok(())
+    }
+
+    /// Append a compressed escrow to the sender's tree and deposit its lamports.
+    ///
+    /// - `amount` is the number of lamports escrowed, within the protocol limits.
+    /// - `receiver` is the wallet of the email recipient, the only one allowed to claim.
+    /// - `ttl_seconds` is the reply window before the sender can refund, within protocol limits.
+    /// - `proof` is the sibling path of the empty leaf at the tree's `next_index`.
+    pub fn append_compressed_escrow(
+        ctx: Context<AppendCompressedEscrow>,
+        thread_id: [u8; 32],
+        amount: u64,
+        receiver: Pubkey,
+        ttl_seconds: i64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let tree = &mut ctx.accounts.tree;
+        let clock = Clock::get()?;
+        let sender = ctx.accounts.sender.key();
+        let expires_at = expiry(clock.unix_timestamp, ttl_seconds)?;
+        let index = tree.next_index;
+
+        // Verify the amount is within the protocol limits.
+        ctx.accounts.config.check_escrow_amount(amount)?;
+
+        // Fill the next empty slot with the escrow leaf.
+        let leaf = escrow_leaf(&sender, &receiver, &thread_id, amount, expires_at);
+        tree.root = replace_leaf(&tree.root, EMPTY_LEAF, leaf, index, &proof)?;
+        tree.next_index = index
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        // Transfer lamports from the sender to the tree PDA, which pools all deposits.
+        let ix = system_instruction::transfer(&sender, &tree.key(), amount);
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.sender.to_account_info(),
+                ctx.accounts.tree.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Leaves live off-chain, so publish everything needed to rebuild them.
+        emit_cpi!(CompressedEscrowAppended {
+            tree: ctx.accounts.tree.key(),
+            index,
+            sender,
+            receiver,
+            thread_id,
+            amount,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a compressed escrow by proving its leaf, which is then cleared.
+    ///
+    /// Like `register_and_claim`, only escrows that have not expired can be claimed.
+    /// - `sender_pubkey` is needed to derive the tree PDA.
+    /// - `index`, `thread_id`, `amount` and `expires_at` must match the appended leaf.
+    #[allow(clippy::too_many_arguments)]
+    pub fn claim_compressed_escrow(
+        ctx: Context<ClaimCompressedEscrow>,
+        sender_pubkey: Pubkey,
+        index: u32,
+        thread_id: [u8; 32],
+        amount: u64,
+        expires_at: i64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let tree = &mut ctx.accounts.tree;
+        let clock = Clock::get()?;
+
+        // Verify the escrow has not expired, so claims cannot race its refund.
+        // `expires_at` is committed to by the leaf, so it cannot be forged.
+        require!(
+            clock.unix_timestamp < expires_at,
+            EscrowError::AlreadyExpired
+        );
+
+        // Clear the leaf so it cannot be claimed or refunded again.
+        // The leaf commits to the receiver, so only they can produce a matching proof.
+        let receiver = ctx.accounts.receiver.key();
+        let leaf = escrow_leaf(&sender_pubkey, &receiver, &thread_id, amount, expires_at);
+        tree.root = replace_leaf(&tree.root, leaf, EMPTY_LEAF, index, &proof)?;
+
+        // Pay the receiver out of the pooled tree lamports.
+        transfer_lamports(
+            &ctx.accounts.tree.to_account_info(),
+            &ctx.accounts.receiver.to_account_info(),
+            amount,
+        )?;
+
+        emit_cpi!(CompressedEscrowSettled {
+            tree: ctx.accounts.tree.key(),
+            index,
+            recipient: ctx.accounts.receiver.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Refund an expired compressed escrow back to the sender.
+    ///
+    /// - `index`, `thread_id`, `amount`, `expires_at` and `receiver` must match the appended leaf.
+    #[allow(clippy::too_many_arguments)]
+    pub fn refund_compressed_escrow(
+        ctx: Context<RefundCompressedEscrow>,
+        index: u32,
+        thread_id: [u8; 32],
+        amount: u64,
+        expires_at: i64,
+        receiver: Pubkey,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let tree = &mut ctx.accounts.tree;
+        let clock = Clock::get()?;
+
+        // Verify the escrow has expired.
+        require!(
+            clock.unix_timestamp >= expires_at,
+            EscrowError::NotExpired
+        );
+
+        // Clear the leaf so it cannot be claimed or refunded again.
+        let sender = ctx.accounts.sender.key();
+        let leaf = escrow_leaf(&sender, &receiver, &thread_id, amount, expires_at);
+        tree.root = replace_leaf(&tree.root, leaf, EMPTY_LEAF, index, &proof)?;
+
+        // Return the lamports from the pooled tree to the sender.
+        transfer_lamports(
+            &ctx.accounts.tree.to_account_info(),
+            &ctx.accounts.sender.to_account_info(),
+            amount,
+        )?;
+
+        emit_cpi!(CompressedEscrowSettled {
+            tree: ctx.accounts.tree.key(),
+            index,
+            recipient: ctx.accounts.sender.key(),
+        });
+
+        Ok(())
+    }
+}
+
+/// Compute the expiry timestamp for a reply window, enforcing the protocol limits.
+fn expiry(now: i64, ttl_seconds: i64) -> Result<i64> {
+    require!(
+        (MIN_TTL_SECONDS..=MAX_TTL_SECONDS).contains(&ttl_seconds),
+        EscrowError::InvalidTtl
+    );
+    now.checked_add(ttl_seconds)
+        .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+}
+
+/// Account an escrow is refunded to: the sender, or the third-party payer or
+/// beneficiary the refund belongs to.
+fn refund_destination<'info>(
+    escrow: &Escrow,
+    sender: AccountInfo<'info>,
+    refund_beneficiary: Option<&UncheckedAccount<'info>>,
+) -> Result<AccountInfo<'info>> {
+    if escrow.refund_recipient() == escrow.sender {
+        return Ok(sender);
+    }
+    let beneficiary = refund_beneficiary.ok_or(EscrowError::InvalidBeneficiary)?;
+    Ok(beneficiary.to_account_info())
+}
+
+/// Move lamports out of a program-owned account with checked arithmetic.
+fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let mut from_lamports = from.try_borrow_mut_lamports()?;
+    let mut to_lamports = to.try_borrow_mut_lamports()?;
+
+    **from_lamports = from_lamports
+        .checked_sub(amount)
+        .ok_or(EscrowError::InsufficientFunds)?;
+    **to_lamports = to_lamports
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Close a program account, moving its remaining lamports (rent included) to `destination`.
+fn close_account<'info>(account: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    transfer_lamports(account, destination, account.lamports())?;
+    account.assign(&system_program::ID);
+    account.resize(0)?;
+
+    Ok(())
+}
+
+/// Write the canonical thread hash to the Memo program as `solmail:<hex thread_id>`.
+fn log_thread_memo<'info>(memo_program: &Program<'info, Memo>, thread_id: &[u8; 32]) -> Result<()> {
+    let hex: String = thread_id.iter().map(|byte| format!("{byte:02x}")).collect();
+    memo::build_memo(
+        CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+        format!("solmail:{hex}").as_bytes(),
+    )
+}
+
+/// Escrow account storing all data needed to manage the incentive.
+#[account]
+#[derive(Debug)]
+pub struct Escrow {
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Wallet allowed to claim the funds (set at initialization).
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Amount of lamports escrowed.
+    pub amount: u64,
+    /// Unix timestamp when the escrow was created.
+    pub created_at: i64,
+    /// Unix timestamp after which the sender can refund.
+    pub expires_at: i64,
+    /// Current status of the escrow.
+    pub status: EscrowStatus,
+    /// PDA bump.
+    pub bump: u8,
+    /// Automation thread allowed to crank the refund (default = any cranker).
+    pub refund_automation: Pubkey,
+    /// Bounty in US cents for USD-denominated escrows (0 = lamport-denominated).
+    pub usd_cents: u64,
+    /// Unix timestamp after which replies earn a reduced payout (at most `expires_at`).
+    pub reply_deadline: i64,
+    /// Share of the payout, in basis points, refunded like unclaimed funds for late replies.
+    pub late_haircut_bps: u16,
+    /// Extra lamports paid only for replies by `bonus_deadline` (0 = no bonus).
+    pub bonus: u64,
+    /// Unix timestamp after which the bonus returns to the sender.
+    pub bonus_deadline: i64,
+    /// SHA-256 of the email subject line (all zeroes when not committed).
+    pub subject_hash: [u8; 32],
+    /// Digest of the email attachments (all zeroes when not committed).
+    pub attachment_hash: [u8; 32],
+    /// SHA-256 of the predicate the reply must satisfy (all zeroes when none).
+    pub reply_commitment: [u8; 32],
+    /// Whether the receiver revealed a preimage matching `reply_commitment`.
+    pub reply_verified: bool,
+    /// Lamports reimbursing the claimant's transaction fees, paid on top of the payout.
+    pub claim_fee_buffer: u64,
+    /// Minimum reply quality score for a full payout (0 = no bar).
+    pub quality_bar: u8,
+    /// Share of the payout, in basis points, released for replies below the bar.
+    pub low_quality_payout_bps: u16,
+    /// Verified quality score of the reply.
+    pub quality_score: u8,
+    /// Whether the platform attested `quality_score`.
+    pub quality_attested: bool,
+    /// Wallet receiving the funds if the escrow expires unclaimed (default = payer).
+    pub refund_beneficiary: Pubkey,
+    /// Slot after which the sender can refund, replacing `expires_at` (0 = unix time).
+    pub expiry_slot: u64,
+    /// Hash of the recipient's email for escrows bound to a wallet later (all zeroes when unused).
+    pub recipient_email_hash: [u8; 32],
+    /// Layout version (`ESCROW_VERSION` at creation); older escrows go through `upgrade_escrow`.
+    pub version: u8,
+    /// Mint of the escrowed token (default = native SOL).
+    pub token_mint: Pubkey,
+    /// Wallet that referred the sender (default = none).
+    pub referrer: Pubkey,
+    /// Unix timestamp a dispute was opened on the escrow (0 = none).
+    pub disputed_at: i64,
+    /// Platform-defined reason code of the dispute.
+    pub dispute_reason: u8,
+    /// Unix timestamp the escrow was claimed or refunded (0 while open).
+    pub settled_at: i64,
+    /// Slot TTL requested at creation (0 when expiring by unix time).
+    pub ttl_slots: u64,
+    /// Wallet that paid for the escrow; unclaimed funds, withheld shares of late or
+    /// low-quality replies, and the rent return to it unless the self-funding
+    /// sender chose a refund beneficiary.
+    pub payer: Pubkey,
+    /// Unix timestamp the receiver started a two-phase release with `begin_claim` (0 = none).
+    pub claim_started_at: i64,
+}
+
+impl Escrow {
+    /// Size of the Escrow account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // sender
+        32 + // receiver
+        32 + // thread_id
+        8 + // amount
+        8 + // created_at
+        8 + // expires_at
+        1 + // status
+        1 + // bump
+        32 + // refund_automation
+        8 + // usd_cents
+        8 + // reply_deadline
+        2 + // late_haircut_bps
+        8 + // bonus
+        8 + // bonus_deadline
+        32 + // subject_hash
+        32 + // attachment_hash
+        32 + // reply_commitment
+        1 + // reply_verified
+        8 + // claim_fee_buffer
+        1 + // quality_bar
+        2 + // low_quality_payout_bps
+        1 + // quality_score
+        1 + // quality_attested
+        32 + // refund_beneficiary
+        8 + // expiry_slot
+        32 + // recipient_email_hash
+        1 + // version
+        32 + // token_mint
+        32 + // referrer
+        8 + // disputed_at
+        1 + // dispute_reason
+        8 + // settled_at
+        8 + // ttl_slots
+        32 + // payer
+        8; // claim_started_at
+
+    /// Size of a v1 escrow, as first deployed before the `version` field existed.
+    pub const V1_LEN: usize =
+        32 + // sender
+        32 + // receiver
+        32 + // thread_id
+        8 + // amount
+        8 + // created_at
+        8 + // expires_at
+        1 + // status
+        1; // bump
+
+    /// Populate a freshly created escrow in Pending status.
+    #[allow(clippy::too_many_arguments)]
+    fn open(
+        &mut self,
+        sender: Pubkey,
+        receiver: Pubkey,
+        thread_id: [u8; 32],
+        amount: u64,
+        now: i64,
+        ttl_seconds: i64,
+        bump: u8,
+    ) -> Result<()> {
+        self.sender = sender;
+        self.receiver = receiver;
+        self.thread_id = thread_id;
+        self.amount = amount;
+        self.created_at = now;
+        self.expires_at = expiry(now, ttl_seconds)?;
+        self.status = EscrowStatus::Pending;
+        self.bump = bump;
+        self.refund_automation = Pubkey::default(); // any cranker until scheduled
+        self.usd_cents = 0; // denominated in lamports
+        self.reply_deadline = self.expires_at; // full payout until expiry
+        self.late_haircut_bps = 0;
+        self.bonus = 0;
+        self.bonus_deadline = 0;
+        self.subject_hash = [0; 32];
+        self.attachment_hash = [0; 32];
+        self.reply_commitment = [0; 32];
+        self.reply_verified = false;
+        self.claim_fee_buffer = 0;
+        self.quality_bar = 0;
+        self.low_quality_payout_bps = 0;
+        self.quality_score = 0;
+        self.quality_attested = false;
+        self.refund_beneficiary = Pubkey::default(); // refunds go to the sender
+        self.expiry_slot = 0; // expires by unix time
+        self.recipient_email_hash = [0; 32];
+        self.version = ESCROW_VERSION;
+        self.token_mint = Pubkey::default(); // native SOL
+        self.referrer = Pubkey::default();
+        self.disputed_at = 0;
+        self.dispute_reason = 0;
+        self.settled_at = 0;
+        self.ttl_slots = 0;
+        self.payer = sender; // self-funded
+        self.claim_started_at = 0;
+
+        Ok(())
+    }
+
+    /// Make refunds depend on the slot rather than the validator timestamp.
+    ///
+    /// A zero `ttl_slots` keeps the unix-time expiry.
+    fn set_slot_expiry(&mut self, now_slot: u64, ttl_slots: u64) -> Result<()> {
+        if ttl_slots == 0 {
+            return Ok(());
+        }
+
+        let min_slots = MIN_TTL_SECONDS as u64 * 1_000 / SLOT_DURATION_MS;
+        let max_slots = MAX_TTL_SECONDS as u64 * 1_000 / SLOT_DURATION_MS;
+        require!(
+            (min_slots..=max_slots).contains(&ttl_slots),
+            EscrowError::InvalidTtl
+        );
+        self.expiry_slot = now_slot
+            .checked_add(ttl_slots)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.ttl_slots = ttl_slots;
+
+        Ok(())
+    }
+
+    /// Whether the escrow was opened with these terms, so a retried create can
+    /// succeed without funding it again.
+    ///
+    /// The amount is compared by the caller, as USD escrows are priced at creation.
+    fn opened_with(
+        &self,
+        receiver: Pubkey,
+        ttl_seconds: i64,
+        reply_window_seconds: i64,
+        late_haircut_bps: u16,
+        ttl_slots: u64,
+        conditions: &ClaimConditions,
+    ) -> Result<bool> {
+        // Replay the creation against the stored escrow's own creation time.
+        let mut expected = self.clone();
+        expected.open(
+            self.sender,
+            receiver,
+            self.thread_id,
+            self.amount,
+            self.created_at,
+            ttl_seconds,
+            self.bump,
+        )?;
+        expected.set_reply_terms(reply_window_seconds, late_haircut_bps)?;
+        expected.set_claim_conditions(conditions)?;
+
+        Ok(self.status == EscrowStatus::Pending
+            && self.receiver == expected.receiver
+            && self.expires_at == expected.expires_at
+            && self.reply_deadline == expected.reply_deadline
+            && self.late_haircut_bps == expected.late_haircut_bps
+            && self.ttl_slots == ttl_slots
+            && self.reply_commitment == expected.reply_commitment
+            && self.quality_bar == expected.quality_bar
+            && self.low_quality_payout_bps == expected.low_quality_payout_bps)
+    }
+
+    /// Apply the reply conditions chosen at creation.
+    fn set_claim_conditions(&mut self, conditions: &ClaimConditions) -> Result<()> {
+        require!(
+            conditions.low_quality_payout_bps as u64 <= BPS_DENOMINATOR,
+            EscrowError::InvalidReplyTerms
+        );
+        self.reply_commitment = conditions.reply_commitment;
+        self.quality_bar = conditions.quality_bar;
+        self.low_quality_payout_bps = conditions.low_quality_payout_bps;
+
+        Ok(())
+    }
+
+    /// Wallet unclaimed funds and the settled escrow's rent return to: the refund
+    /// beneficiary, or the payer when none was chosen.
+    fn refund_recipient(&self) -> Pubkey {
+        if self.refund_beneficiary == Pubkey::default() {
+            self.payer
+        } else {
+            self.refund_beneficiary
+        }
+    }
+
+    /// Whether the sender can refund the escrow at `clock`.
+    fn is_expired(&self, clock: &Clock) -> bool {
+        if self.expiry_slot > 0 {
+            clock.slot >= self.expiry_slot
+        } else {
+            clock.unix_timestamp >= self.expires_at
+        }
+    }
+
+    /// Verify the escrow can be claimed at `clock`: pending and unexpired, or with
+    /// a release already started by `begin_claim` before it expired.
+    fn check_claimable(&self, clock: &Clock) -> Result<()> {
+        require!(
+            self.status == EscrowStatus::Pending || self.status == EscrowStatus::Claiming,
+            EscrowError::InvalidStatus
+        );
+        require!(
+            self.status == EscrowStatus::Claiming || !self.is_expired(clock),
+            EscrowError::AlreadyExpired
+        );
+
+        Ok(())
+    }
+
+    /// Set a reply deadline ahead of expiry, after which claims lose a haircut.
+    ///
+    /// A zero `reply_window_seconds` keeps the full payout until expiry.
+    fn set_reply_terms(&mut self, reply_window_seconds: i64, late_haircut_bps: u16) -> Result<()> {
+        require!(
+            late_haircut_bps as u64 <= BPS_DENOMINATOR,
+            EscrowError::InvalidReplyTerms
+        );
+        if reply_window_seconds == 0 {
+            return Ok(());
+        }
+
+        let reply_deadline = self
+            .created_at
+            .checked_add(reply_window_seconds)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(
+            reply_window_seconds > 0 && reply_deadline <= self.expires_at,
+            EscrowError::InvalidReplyTerms
+        );
+        self.reply_deadline = reply_deadline;
+        self.late_haircut_bps = late_haircut_bps;
+
+        Ok(())
+    }
+
+    /// Push the expiry back by `extension_seconds`, within the maximum lifetime.
+    ///
+    /// Slot-based escrows move their expiry slot by the equivalent number of slots.
+    pub fn extend(&mut self, extension_seconds: i64) -> Result<()> {
+        // Verify the new expiry moves forward within the maximum lifetime.
+        let expires_at = self
+            .expires_at
+            .checked_add(extension_seconds)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        let lifetime = expires_at
+            .checked_sub(self.created_at)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(
+            extension_seconds > 0 && lifetime <= MAX_ESCROW_LIFETIME_SECONDS,
+            EscrowError::InvalidTtl
+        );
+
+        if self.expiry_slot > 0 {
+            self.expiry_slot = (extension_seconds as u64)
+                .checked_mul(1_000)
+                .map(|millis| millis / SLOT_DURATION_MS)
+                .and_then(|slots| self.expiry_slot.checked_add(slots))
+                .ok_or(EscrowError::ArithmeticOverflow)?;
+        }
+        self.expires_at = expires_at;
+
+        Ok(())
+    }
+
+    /// Lamports the escrow still counts towards its thread's pending total.
+    pub fn pending_amount(&self) -> Result<u64> {
+        self.amount
+            .checked_add(self.bonus)
+            .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+    }
+
+    /// Lamports refunded from `payout` when the reply scored below the quality bar.
+    pub fn quality_slash(&self, payout: u64) -> Result<u64> {
+        if self.quality_bar == 0 || self.quality_score >= self.quality_bar {
+            return Ok(0);
+        }
+        let released = (payout as u128)
+            .checked_mul(self.low_quality_payout_bps as u128)
+            .and_then(|value| value.checked_div(BPS_DENOMINATOR as u128))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        payout
+            .checked_sub(released as u64)
+            .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+    }
+
+    /// Lamports withheld from a claim of `payout` made at `now`.
+    pub fn late_haircut(&self, payout: u64, now: i64) -> Result<u64> {
+        if now <= self.reply_deadline {
+            return Ok(0);
+        }
+        let haircut = (payout as u128)
+            .checked_mul(self.late_haircut_bps as u128)
+            .and_then(|value| value.checked_div(BPS_DENOMINATOR as u128))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(haircut as u64)
+    }
+
+    /// Lamports of a claim paying out `payout` (bonus included) withheld from the
+    /// base amount and refunded to whoever funded it, `refund_recipient()`.
+    ///
+    /// Late replies return the haircut on the base payout, low-quality replies the
+    /// slashed share.
+    pub fn refund_share(&self, payout: u64, now: i64) -> Result<u64> {
+        let base_payout = payout
+            .checked_sub(self.bonus)
+            .ok_or(EscrowError::InsufficientFunds)?;
+        let late_haircut = self.late_haircut(base_payout, now)?;
+        let quality_slash = self.quality_slash(
+            base_payout
+                .checked_sub(late_haircut)
+                .ok_or(EscrowError::ArithmeticOverflow)?,
+        )?;
+        late_haircut
+            .checked_add(quality_slash)
+            .ok_or_else(|| error!(EscrowError::ArithmeticOverflow))
+    }
+
+    /// Early-reply bonus returned to the sender who added it, once its window has passed.
+    pub fn forfeited_bonus(&self, now: i64) -> u64 {
+        if now > self.bonus_deadline {
+            self.bonus
+        } else {
+            0
+        }
+    }
+}
+
+/// Platform-wide settings controlled by the admin.
+#[account]
+#[derive(Debug)]
+pub struct PlatformConfig {
+    /// Wallet allowed to update the config.
+    pub admin: Pubkey,
+    /// Platform key whose co-signature attests that a wallet genuinely replied.
+    pub attestor: Pubkey,
+    /// Platform key allowed to cancel abusive escrows before expiry.
+    pub moderator: Pubkey,
+    /// PDA bump.
+    pub bump: u8,
+    /// Smallest amount of lamports a new escrow may hold.
+    pub min_escrow_amount: u64,
+    /// Largest amount of lamports a new escrow may hold (0 = no cap).
+    pub max_escrow_amount: u64,
+}
+
+impl PlatformConfig {
+    /// Size of the PlatformConfig account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // admin
+        32 + // attestor
+        32 + // moderator
+        1 + // bump
+        8 + // min_escrow_amount
+        8; // max_escrow_amount
+
+    fn set_escrow_limits(&mut self, min_escrow_amount: u64, max_escrow_amount: u64) -> Result<()> {
+        require!(
+            max_escrow_amount == 0 || min_escrow_amount <= max_escrow_amount,
+            EscrowError::InvalidAmountLimits
+        );
+
+        self.min_escrow_amount = min_escrow_amount;
+        self.max_escrow_amount = max_escrow_amount;
+
+        Ok(())
+    }
+
+    /// Reject escrow amounts outside the protocol limits.
+    fn check_escrow_amount(&self, amount: u64) -> Result<()> {
+        require!(
+            amount >= self.min_escrow_amount
+                && (self.max_escrow_amount == 0 || amount <= self.max_escrow_amount),
+            EscrowError::AmountOutOfRange
+        );
+
+        Ok(())
+    }
+}
+
+/// Audit entry recording why a moderator cancelled an escrow.
+#[account]
+#[derive(Debug)]
+pub struct ModerationRecord {
+    /// Escrow that was cancelled.
+    pub escrow: Pubkey,
+    /// Moderator who cancelled it.
+    pub moderator: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Platform-defined moderation reason.
+    pub reason_code: u8,
+    /// Unix timestamp of the cancellation.
+    pub cancelled_at: i64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl ModerationRecord {
+    /// Size of the ModerationRecord account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // escrow
+        32 + // moderator
+        32 + // thread_id
+        1 + // reason_code
+        8 + // cancelled_at
+        1; // bump
+}
+
+/// Reply history of a recipient, used to award reputation badges.
+#[account]
+#[derive(Debug)]
+pub struct RecipientStats {
+    /// Wallet the stats belong to.
+    pub recipient: Pubkey,
+    /// Number of escrows the recipient has claimed by replying.
+    pub reply_count: u64,
+    /// Sum of seconds between escrow creation and claim, across all replies.
+    pub total_response_time: i64,
+    /// Highest badge milestone already minted to the recipient.
+    pub badge_level: u8,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl RecipientStats {
+    /// Size of the RecipientStats account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // recipient
+        8 + // reply_count
+        8 + // total_response_time
+        1 + // badge_level
+        1; // bump
+
+    /// Count a reply to `escrow`, claimed at `now`.
+    ///
+    /// Escrows the recipient funded themselves are not counted, so reputation
+    /// cannot be earned by paying oneself.
+    fn record_reply(
+        &mut self,
+        recipient: Pubkey,
+        escrow: &Escrow,
+        now: i64,
+        bump: u8,
+    ) -> Result<()> {
+        self.recipient = recipient;
+        self.bump = bump;
+        if escrow.sender == recipient || escrow.payer == recipient {
+            return Ok(());
+        }
+
+        let response_time = now
+            .checked_sub(escrow.created_at)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.reply_count = self
+            .reply_count
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.total_response_time = self
+            .total_response_time
+            .checked_add(response_time)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+}
+
+/// Conditions a reply must meet before an escrow can be claimed, fixed at creation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ClaimConditions {
+    /// SHA-256 of a predicate preimage (e.g. salted required keywords or expected
+    /// form fields) the reply must reveal, or all zeroes for none.
+    pub reply_commitment: [u8; 32],
+    /// Minimum attested reply quality score for a full payout (0 = no bar).
+    pub quality_bar: u8,
+    /// Share of the payout, in basis points, released for replies below the bar;
+    /// the rest is refunded like unclaimed funds.
+    pub low_quality_payout_bps: u16,
+}
+
+/// Payout preview returned by `quote_release`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ReleaseQuote {
+    /// Escrow the quote is for.
+    pub escrow: Pubkey,
+    /// Sender who funded the escrow.
+    pub sender: Pubkey,
+    /// Lamports released by the claim, excluding the escrow's rent.
+    pub gross_amount: u64,
+    /// Lamports reimbursing the claimant's transaction fees.
+    pub claim_fee_buffer: u64,
+    /// Lamports of the base amount refunded to its payer or refund beneficiary
+    /// (late haircut, quality slash).
+    pub refund_share: u64,
+    /// Early-reply bonus returned to the sender because its window has passed.
+    pub forfeited_bonus: u64,
+    /// Net lamports paid to the receiver.
+    pub receiver_amount: u64,
+    /// Unix timestamp the quote was computed at; the split depends on claim time.
+    pub quoted_at: i64,
+}
+
+/// Compact record of a claim and the proof that justified it.
+#[account]
+#[derive(Debug)]
+pub struct ClaimReceipt {
+    /// Escrow that was claimed.
+    pub escrow: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Wallet that signed the claim (the receiver or their delegate).
+    pub claimer: Pubkey,
+    /// Wallet the payout went to.
+    pub receiver: Pubkey,
+    /// Lamports paid to the receiver.
+    pub amount: u64,
+    /// Hash of the reply proof justifying the payout (all zeroes for `release_many`).
+    pub reply_proof_hash: [u8; 32],
+    /// Slot of the claim.
+    pub slot: u64,
+    /// PDA bump.
+    pub bump: u8,
+    /// Hash of the sender-authentication (SPF/DMARC) verdict on the reply (all zeroes when not verified).
+    pub reply_verdict_hash: [u8; 32],
+}
+
+impl ClaimReceipt {
+    /// Size of the ClaimReceipt account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // escrow
+        32 + // thread_id
+        32 + // claimer
+        32 + // receiver
+        8 + // amount
+        32 + // reply_proof_hash
+        8 + // slot
+        1 + // bump
+        32; // reply_verdict_hash
+}
+
+/// Aggregate of all escrows referencing the same email thread.
+///
+/// Lets clients show a thread's pending and released totals with one fetch.
+#[account]
+#[derive(Debug)]
+pub struct Thread {
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Number of escrows ever created for the thread.
+    pub escrow_count: u64,
+    /// Lamports escrowed for the thread and not yet claimed or refunded.
+    pub total_pending: u64,
+    /// Lamports paid out to receivers of the thread's escrows.
+    pub total_released: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl Thread {
+    /// Size of the Thread account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // thread_id
+        8 + // escrow_count
+        8 + // total_pending
+        8 + // total_released
+        1; // bump
+
+    /// Count a newly created escrow of `amount` lamports.
+    fn record_escrow(&mut self, thread_id: [u8; 32], amount: u64, bump: u8) -> Result<()> {
+        self.thread_id = thread_id;
+        self.bump = bump;
+        self.escrow_count = self
+            .escrow_count
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.add_pending(amount)
+    }
+
+    /// Add lamports deposited into one of the thread's escrows.
+    fn add_pending(&mut self, amount: u64) -> Result<()> {
+        self.total_pending = self
+            .total_pending
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Remove a settled escrow's `pending` lamports, `released` of which reached the receiver.
+    fn settle(&mut self, pending: u64, released: u64) -> Result<()> {
+        self.total_pending = self
+            .total_pending
+            .checked_sub(pending)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.total_released = self
+            .total_released
+            .checked_add(released)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Program-wide escrow totals for dashboards and reporting.
+///
+/// Only `Escrow` accounts are counted: compressed escrows, group escrows,
+/// campaigns, bounty pools and auctions keep their own totals. The program takes
+/// no fee on any path, so there is no fee total to report.
+#[account]
+#[derive(Debug)]
+pub struct GlobalStats {
+    /// Number of escrows ever created.
+    pub total_escrows: u64,
+    /// Lamports ever deposited into escrows, including bonuses but not claim fee buffers.
+    pub total_volume: u64,
+    /// Number of escrows refunded (to their payer or refund beneficiary).
+    pub total_refunds: u64,
+    /// Number of escrows claimed by their receivers.
+    pub total_claims: u64,
+    /// Lamports ever paid out to receivers by claims.
+    pub total_claimed: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl GlobalStats {
+    /// Size of the GlobalStats account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        8 + // total_escrows
+        8 + // total_volume
+        8 + // total_refunds
+        8 + // total_claims
+        8 + // total_claimed
+        1; // bump
+
+    /// Count a newly created escrow of `amount` lamports.
+    fn record_escrow(&mut self, amount: u64) -> Result<()> {
+        self.total_escrows = self
+            .total_escrows
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.add_volume(amount)
+    }
+
+    /// Add lamports deposited into an escrow.
+    fn add_volume(&mut self, amount: u64) -> Result<()> {
+        self.total_volume = self
+            .total_volume
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Count a refunded escrow.
+    fn record_refund(&mut self) -> Result<()> {
+        self.total_refunds = self
+            .total_refunds
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Count an escrow claimed with `amount` lamports paid to its receiver.
+    fn record_claim(&mut self, amount: u64) -> Result<()> {
+        self.total_claims = self
+            .total_claims
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.total_claimed = self
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Summary of a recipient's pending escrows.
+///
+/// Lets wallets and email clients show "1.2 SOL waiting across 7 emails" with one fetch.
+#[account]
+#[derive(Debug)]
+pub struct Inbox {
+    /// Wallet the escrows are addressed to.
+    pub recipient: Pubkey,
+    /// Number of escrows awaiting a claim or refund.
+    pub pending_count: u64,
+    /// Lamports held by those escrows.
+    pub total_pending: u64,
+    /// Latest expiry among escrows recorded for the recipient.
+    pub newest_expiry: i64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl Inbox {
+    /// Size of the Inbox account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // recipient
+        8 + // pending_count
+        8 + // total_pending
+        8 + // newest_expiry
+        1; // bump
+
+    /// Count a pending escrow of `amount` lamports addressed to the recipient.
+    fn record_escrow(
+        &mut self,
+        recipient: Pubkey,
+        amount: u64,
+        expires_at: i64,
+        bump: u8,
+    ) -> Result<()> {
+        self.recipient = recipient;
+        self.bump = bump;
+        self.pending_count = self
+            .pending_count
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.record_expiry(expires_at);
+        self.add_pending(amount)
+    }
+
+    /// Add lamports deposited into one of the recipient's pending escrows.
+    fn add_pending(&mut self, amount: u64) -> Result<()> {
+        self.total_pending = self
+            .total_pending
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    fn record_expiry(&mut self, expires_at: i64) {
+        self.newest_expiry = self.newest_expiry.max(expires_at);
+    }
+
+    /// Remove a claimed, refunded or reassigned escrow holding `pending` lamports.
+    fn settle(&mut self, pending: u64) -> Result<()> {
+        self.pending_count = self
+            .pending_count
+            .checked_sub(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.total_pending = self
+            .total_pending
+            .checked_sub(pending)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Wallet a recipient has allowed to claim escrows on their behalf.
+#[account]
+#[derive(Debug)]
+pub struct ClaimDelegate {
+    /// Recipient receiving the claimed funds.
+    pub recipient: Pubkey,
+    /// Wallet allowed to sign claims (default pubkey when revoked).
+    pub delegate: Pubkey,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl ClaimDelegate {
+    /// Size of the ClaimDelegate account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // recipient
+        32 + // delegate
+        1; // bump
+}
+
+/// Per-sender merkle tree holding compressed escrows and their pooled lamports.
+#[account]
+#[derive(Debug)]
+pub struct EscrowTree {
+    /// Wallet whose compressed escrows live in this tree.
+    pub sender: Pubkey,
+    /// Current merkle root over all escrow leaves.
+    pub root: [u8; 32],
+    /// Index of the next empty leaf.
+    pub next_index: u32,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl EscrowTree {
+    /// Size of the EscrowTree account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // sender
+        32 + // root
+        4 + // next_index
+        1; // bump
+}
+
+/// Accounts required to initialize an escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32], amount: u64, receiver: Pubkey)]
+pub struct InitializeEscrow<'info> {
+    /// The sender recorded on the escrow, approving it by signing.
+    pub sender: Signer<'info>,
+
+    /// Pays the escrowed lamports and rent; may differ from the sender.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// PDA that will hold the escrowed lamports and state; an existing escrow is
+    /// accepted so retried creates are idempotent.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Escrow::LEN,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread, created by its first escrow.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Thread::LEN,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows, created by their first escrow.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Inbox::LEN,
+        seeds = [b"inbox", receiver.as_ref()],
+        bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// System program for creating the account and transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Optional Memo program used to record the thread hash.
+    pub memo_program: Option<Program<'info, Memo>>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required to initialize a USD-denominated escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32], usd_cents: u64, receiver: Pubkey)]
+pub struct InitializeUsdEscrow<'info> {
+    /// The sender recorded on the escrow, approving it by signing.
+    pub sender: Signer<'info>,
+
+    /// Pays the escrowed lamports and rent; may differ from the sender.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// PDA that will hold the escrowed lamports and state; an existing escrow is
+    /// accepted so retried creates are idempotent.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Escrow::LEN,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread, created by its first escrow.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Thread::LEN,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows, created by their first escrow.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Inbox::LEN,
+        seeds = [b"inbox", receiver.as_ref()],
+        bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// CHECK: Pyth SOL/USD `PriceUpdateV2` account, validated in `read_sol_usd_price`.
+    pub price_update: UncheckedAccount<'info>,
+
+    /// System program for creating the account and transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Optional Memo program used to record the thread hash.
+    pub memo_program: Option<Program<'info, Memo>>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required to initialize an escrow funded with wrapped SOL.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32], amount: u64, receiver: Pubkey)]
+pub struct InitializeEscrowFromWsol<'info> {
+    /// The sender recorded on the escrow, approving it by signing.
+    pub sender: Signer<'info>,
+
+    /// Pays the rent of the escrow accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// PDA that will hold the escrowed lamports and state; an existing escrow is
+    /// accepted so retried creates are idempotent.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Escrow::LEN,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread, created by its first escrow.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Thread::LEN,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows, created by their first escrow.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Inbox::LEN,
+        seeds = [b"inbox", receiver.as_ref()],
+        bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Sender's wSOL token account the escrow is funded from.
+    #[account(
+        mut,
+        token::mint = native_mint,
+        token::authority = sender,
+        token::token_program = token_program,
+    )]
+    pub sender_wsol: InterfaceAccount<'info, TokenAccount>,
+
+    /// Temporary wSOL vault owned by the escrow, closed within the instruction.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"wsol_vault", escrow.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = escrow,
+        token::token_program = token_program,
+    )]
+    pub wsol_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The native (wrapped SOL) mint.
+    #[account(address = anchor_spl::token::spl_token::native_mint::ID)]
+    pub native_mint: InterfaceAccount<'info, Mint>,
+
+    /// SPL Token program owning the wSOL accounts.
+    pub token_program: Program<'info, Token>,
+
+    /// System program for creating the accounts.
+    pub system_program: Program<'info, System>,
+
+    /// Optional Memo program used to record the thread hash.
+    pub memo_program: Option<Program<'info, Memo>>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required for the registered receiver to claim escrowed funds.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct RegisterAndClaim<'info> {
+    /// The receiver, or their claim delegate, signing the claim.
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    /// The receiver the funds are paid to.
+    #[account(mut)]
+    pub receiver: SystemAccount<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread.
+    #[account(
+        mut,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump = thread.bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Reply history of the receiver, created on their first claim.
+    #[account(
+        init_if_needed,
+        payer = claimant,
+        space = 8 + RecipientStats::LEN,
+        seeds = [b"recipient_stats", receiver.key().as_ref()],
+        bump,
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// System program for creating the stats and receipt accounts.
+    pub system_program: Program<'info, System>,
+
+    /// Optional Memo program used to record the thread hash.
+    pub memo_program: Option<Program<'info, Memo>>,
+
+    /// CHECK: Pyth SOL/USD `PriceUpdateV2` account, required for USD-denominated
+    /// escrows and validated in `read_sol_usd_price`.
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// Platform config, required to claim open bounties.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, PlatformConfig>>,
+
+    /// Platform attestor co-signing open bounty claims.
+    pub attestor: Option<Signer<'info>>,
+
+    /// The receiver's claim delegate, required when the claimant is not the receiver.
+    #[account(
+        seeds = [b"claim_delegate", receiver.key().as_ref()],
+        bump = claim_delegate.bump,
+    )]
+    pub claim_delegate: Option<Account<'info, ClaimDelegate>>,
+
+    /// The sender, required to receive a forfeited bonus, or the withheld share of a
+    /// late or low-quality reply when refunds go to them.
+    #[account(mut, address = sender_pubkey @ EscrowError::SenderMismatch)]
+    pub sender: Option<SystemAccount<'info>>,
+
+    /// CHECK: the escrow's third-party payer or refund beneficiary, required to
+    /// receive the withheld share of a late or low-quality reply when refunds do
+    /// not go to the sender.
+    #[account(mut, address = escrow.refund_recipient() @ EscrowError::InvalidBeneficiary)]
+    pub refund_beneficiary: Option<UncheckedAccount<'info>>,
+
+    /// Receipt of this claim, keyed by the escrow and its creation time.
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + ClaimReceipt::LEN,
+        seeds = [
+            b"claim_receipt",
+            escrow.key().as_ref(),
+            &escrow.created_at.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub claim_receipt: Account<'info, ClaimReceipt>,
+}
+
+/// Accounts required to quote a claim's payout.
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct QuoteRelease<'info> {
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required to refund escrowed funds.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct RefundEscrow<'info> {
+    /// The sender who funded the escrow (only they can refund).
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread.
+    #[account(
+        mut,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump = thread.bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// System program for closing the account.
+    pub system_program: Program<'info, System>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: the escrow's third-party payer or refund beneficiary, required when
+    /// refunds do not go to the sender.
+    #[account(mut, address = escrow.refund_recipient() @ EscrowError::InvalidBeneficiary)]
+    pub refund_beneficiary: Option<UncheckedAccount<'info>>,
+}
+
+/// Accounts required to schedule an automated refund.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct ScheduleRefund<'info> {
+    /// The sender who funded the escrow.
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required to commit escrow metadata.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct CommitEscrowMetadata<'info> {
+    /// The sender who funded the escrow.
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required for the receiver to reveal a reply predicate preimage.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct SubmitReplyPreimage<'info> {
+    /// The receiver the escrow is bound to.
+    pub receiver: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required for the platform to attest a reply's quality score.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct AttestReplyQuality<'info> {
+    /// Platform attestor relaying the verified score.
+    pub attestor: Signer<'info>,
+
+    /// Platform config naming the attestor.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.attestor == attestor.key() @ EscrowError::InvalidAttestation,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required to fund an escrow's claim fee buffer.
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct FundClaimFee<'info> {
+    /// The sender who funded the escrow.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// System program for transferring lamports.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to add an early-reply bonus to an escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct AddEarlyReplyBonus<'info> {
+    /// The sender who funded the escrow.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread.
+    #[account(
+        mut,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump = thread.bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// System program for transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+/// Accounts required to set an escrow's refund beneficiary.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct SetRefundBeneficiary<'info> {
+    /// The sender who funded the escrow.
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required to extend an escrow's expiry.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct ExtendEscrow<'info> {
+    /// The sender who funded the escrow.
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+}
+
+/// Accounts required to upgrade an escrow to the current layout.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct UpgradeEscrow<'info> {
+    /// The sender who funded the escrow, paying the extra rent.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// CHECK: escrow PDA with an older layout, which the current one cannot
+    /// deserialize; its layout is verified in `migrate_escrow`.
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump,
+    )]
+    pub escrow: UncheckedAccount<'info>,
+
+    /// Aggregate of every escrow referencing the thread; baseline escrows were
+    /// never recorded in one.
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + Thread::LEN,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of pending open claims: the baseline only set the receiver on
+    /// claim, so every baseline escrow still pending is addressed to no one.
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + Inbox::LEN,
+        seeds = [b"inbox", Pubkey::default().as_ref()],
+        bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// System program for transferring the extra rent.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a keeper to refund an expired escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct CrankRefundEscrow<'info> {
+    /// The keeper executing the refund.
+    pub cranker: Signer<'info>,
+
+    /// The sender who funded the escrow and receives the refund.
+    #[account(mut)]
+    pub sender: SystemAccount<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+        has_one = sender,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread.
+    #[account(
+        mut,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump = thread.bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: the escrow's third-party payer or refund beneficiary, required when
+    /// refunds do not go to the sender.
+    #[account(mut, address = escrow.refund_recipient() @ EscrowError::InvalidBeneficiary)]
+    pub refund_beneficiary: Option<UncheckedAccount<'info>>,
+}
+
+/// Accounts required to create the platform config.
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// The program's upgrade authority, becoming the config admin.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// PDA holding the platform settings.
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PlatformConfig::LEN,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// This program, used to locate its program data account.
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::SolmailEscrow>,
+
+    /// Program data holding the upgrade authority.
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ EscrowError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to update the platform config.
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// The current config admin.
+    pub admin: Signer<'info>,
+
+    /// PDA holding the platform settings.
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ EscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required to address an escrow to an email hash.
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct SetRecipientEmailHash<'info> {
+    /// The sender who funded the escrow.
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required for the platform to bind an email-addressed escrow to a wallet.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32], receiver: Pubkey)]
+pub struct BindRecipient<'info> {
+    /// Platform attestor vouching that `receiver` owns the email.
+    #[account(mut)]
+    pub attestor: Signer<'info>,
+
+    /// Platform config naming the attestor.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.attestor == attestor.key() @ EscrowError::InvalidAttestation,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Summary of unbound escrows, kept under the default pubkey.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Summary of the bound receiver's pending escrows, created if needed.
+    #[account(
+        init_if_needed,
+        payer = attestor,
+        space = 8 + Inbox::LEN,
+        seeds = [b"inbox", receiver.as_ref()],
+        bump,
+    )]
+    pub new_inbox: Account<'info, Inbox>,
+
+    /// System program for creating the receiver's inbox.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to reassign an escrow's receiver.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32], new_receiver: Pubkey)]
+pub struct ReassignRecipient<'info> {
+    /// The current receiver, or the platform attestor.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Summary of the new receiver's pending escrows, created if needed.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Inbox::LEN,
+        seeds = [b"inbox", new_receiver.as_ref()],
+        bump,
+    )]
+    pub new_inbox: Account<'info, Inbox>,
+
+    /// System program for creating the new receiver's inbox.
+    pub system_program: Program<'info, System>,
+
+    /// Platform config, required when the attestor reassigns.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, PlatformConfig>>,
+}
+
+/// Accounts required to set a recipient's claim delegate.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetClaimDelegate<'info> {
+    /// The recipient designating the delegate.
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// PDA recording the recipient's delegate.
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + ClaimDelegate::LEN,
+        seeds = [b"claim_delegate", recipient.key().as_ref()],
+        bump,
+    )]
+    pub claim_delegate: Account<'info, ClaimDelegate>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for the platform moderator to cancel an escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct ModeratorCancel<'info> {
+    /// The platform moderator, paying for the audit record.
+    #[account(mut)]
+    pub moderator: Signer<'info>,
+
+    /// Platform config naming the moderator.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.moderator == moderator.key() @ EscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// The sender who funded the escrow and receives the refund.
+    #[account(mut)]
+    pub sender: SystemAccount<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+        has_one = sender,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// Aggregate of every escrow referencing the thread.
+    #[account(
+        mut,
+        seeds = [b"thread", thread_id.as_ref()],
+        bump = thread.bump,
+    )]
+    pub thread: Account<'info, Thread>,
+
+    /// Summary of the receiver's pending escrows.
+    #[account(
+        mut,
+        seeds = [b"inbox", escrow.receiver.as_ref()],
+        bump = inbox.bump,
+    )]
+    pub inbox: Account<'info, Inbox>,
+
+    /// Audit entry recording the cancellation.
+    #[account(
+        init,
+        payer = moderator,
+        space = 8 + ModerationRecord::LEN,
+        seeds = [b"moderation", escrow.key().as_ref(), &escrow.created_at.to_le_bytes()],
+        bump,
+    )]
+    pub moderation_record: Account<'info, ModerationRecord>,
+
+    /// System program for creating the audit record.
+    pub system_program: Program<'info, System>,
+
+    /// Program-wide escrow totals.
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: the escrow's third-party payer or refund beneficiary, required when
+    /// refunds do not go to the sender.
+    #[account(mut, address = escrow.refund_recipient() @ EscrowError::InvalidBeneficiary)]
+    pub refund_beneficiary: Option<UncheckedAccount<'info>>,
+}
+
+/// Accounts required for the sender or receiver to dispute an escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct OpenDispute<'info> {
+    /// The sender or receiver opening the dispute.
+    pub disputant: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required for the moderator to dismiss a dispute.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct ResolveDispute<'info> {
+    /// The platform moderator.
+    pub moderator: Signer<'info>,
+
+    /// Platform config naming the moderator.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.moderator == moderator.key() @ EscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required to mark an expired escrow as Expired.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct ExpireEscrow<'info> {
+    /// Anyone may mark an expired escrow.
+    pub cranker: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required for the receiver to start a two-phase release.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey, thread_id: [u8; 32])]
+pub struct BeginClaim<'info> {
+    /// The receiver the escrow is bound to.
+    pub receiver: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender_pubkey.as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required for the sender to cancel a stale two-phase release.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct CancelStaleClaim<'info> {
+    /// The sender who funded the escrow.
+    pub sender: Signer<'info>,
+
+    /// PDA holding the escrowed lamports and state.
+    #[account(
+        mut,
+        seeds = [b"escrow", sender.key().as_ref(), &thread_id],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+/// Accounts required to create the global stats account.
+#[derive(Accounts)]
+pub struct InitializeGlobalStats<'info> {
+    /// Pays for the stats account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Singleton PDA holding program-wide totals.
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GlobalStats::LEN,
+        seeds = [b"global_stats"],
+        bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to create the soulbound badge mint.
+#[derive(Accounts)]
+pub struct InitializeBadgeMint<'info> {
+    /// Pays for the mint account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: created and initialized as a Token-2022 mint in this instruction.
+    #[account(
+        mut,
+        seeds = [b"badge_mint"],
+        bump,
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+
+    /// Token-2022 program owning the mint.
+    pub token_program: Program<'info, Token2022>,
+
+    /// System program for creating the mint account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to mint reputation badges to a recipient.
+#[derive(Accounts)]
+pub struct ClaimReputationBadge<'info> {
+    /// The recipient claiming their badges.
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// Reply history of the recipient.
+    #[account(
+        mut,
+        seeds = [b"recipient_stats", recipient.key().as_ref()],
+        bump = recipient_stats.bump,
+        has_one = recipient,
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+
+    /// The soulbound badge mint.
+    #[account(
+        mut,
+        seeds = [b"badge_mint"],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub badge_mint: InterfaceAccount<'info, Mint>,
+
+    /// Recipient's badge token account, created on first claim.
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = badge_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub recipient_badge_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token-2022 program owning the badge mint.
+    pub token_program: Program<'info, Token2022>,
+
+    /// Associated token program for creating the badge account.
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program for creating the badge account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to create a sender's compressed escrow tree.
+#[derive(Accounts)]
+pub struct InitializeEscrowTree<'info> {
+    /// The sender owning the tree.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// PDA holding the tree root and pooled lamports.
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + EscrowTree::LEN,
+        seeds = [b"escrow_tree", sender.key().as_ref()],
+        bump,
+    )]
+    pub tree: Account<'info, EscrowTree>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to append a compressed escrow.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AppendCompressedEscrow<'info> {
+    /// The sender funding the escrow.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The sender's compressed escrow tree.
+    #[account(
+        mut,
+        seeds = [b"escrow_tree", sender.key().as_ref()],
+        bump = tree.bump,
+    )]
+    pub tree: Account<'info, EscrowTree>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+
+    /// System program for transferring lamports.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to claim a compressed escrow.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(sender_pubkey: Pubkey)]
+pub struct ClaimCompressedEscrow<'info> {
+    /// The receiver claiming the funds.
+    #[account(mut)]
+    pub receiver: Signer<'info>,
+
+    /// The sender's compressed escrow tree.
+    #[account(
+        mut,
+        seeds = [b"escrow_tree", sender_pubkey.as_ref()],
+        bump = tree.bump,
+    )]
+    pub tree: Account<'info, EscrowTree>,
+}
+
+/// Accounts required to refund a compressed escrow.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundCompressedEscrow<'info> {
+    /// The sender who funded the escrow (only they can refund).
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The sender's compressed escrow tree.
+    #[account(
+        mut,
+        seeds = [b"escrow_tree", sender.key().as_ref()],
+        bump = tree.bump,
+    )]
+    pub tree: Account<'info, EscrowTree>,
+}
+
+/// Emitted when an escrow is created and funded.
+#[event]
+pub struct EscrowInitialized {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Wallet entitled to claim the escrow.
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports deposited into the escrow.
+    pub amount: u64,
+    /// Unix timestamp after which the sender can refund.
+    pub expires_at: i64,
+    /// Unix timestamp after which replies earn a reduced payout.
+    pub reply_deadline: i64,
+}
+
+/// Emitted when the sender commits an escrow to its subject and attachments.
+#[event]
+pub struct EscrowMetadataCommitted {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// SHA-256 of the email subject line.
+    pub subject_hash: [u8; 32],
+    /// Digest of the email attachments.
+    pub attachment_hash: [u8; 32],
+}
+
+/// Emitted when the receiver proves their reply satisfies the sender's predicate.
+#[event]
+pub struct ReplyPredicateVerified {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Wallet entitled to claim the escrow.
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+}
+
+/// Emitted when the platform attests the quality score of a reply.
+#[event]
+pub struct ReplyQualityAttested {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Attested quality score of the reply.
+    pub quality_score: u8,
+    /// Minimum score for a full payout.
+    pub quality_bar: u8,
+}
+
+/// Emitted when the sender adds an early-reply bonus to an escrow.
+#[event]
+pub struct EarlyReplyBonusAdded {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports added as the bonus.
+    pub bonus: u64,
+    /// Unix timestamp after which the bonus returns to the sender.
+    pub bonus_deadline: i64,
+}
+
+/// Emitted when the receiver claims an escrow.
+#[event]
+pub struct EscrowClaimed {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Wallet entitled to claim the escrow.
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports paid out to the receiver.
+    pub amount: u64,
+    /// Subject line commitment the escrow paid for.
+    pub subject_hash: [u8; 32],
+    /// Attachment digest commitment the escrow paid for.
+    pub attachment_hash: [u8; 32],
+}
+
+/// Emitted when an email-addressed escrow is bound to the wallet owning the email.
+#[event]
+pub struct RecipientBound {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Hash of the recipient email the escrow was addressed to.
+    pub recipient_email_hash: [u8; 32],
+    /// Wallet bound as the receiver.
+    pub receiver: Pubkey,
+}
+
+/// Emitted when claim rights to an escrow move to a new receiver.
+#[event]
+pub struct RecipientReassigned {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Receiver before the reassignment.
+    pub previous_receiver: Pubkey,
+    /// Receiver after the reassignment.
+    pub new_receiver: Pubkey,
+    /// Receiver or platform attestor that signed the reassignment.
+    pub authority: Pubkey,
+}
+
+/// Emitted when a recipient sets or revokes their claim delegate.
+#[event]
+pub struct ClaimDelegateSet {
+    /// Recipient delegating their claims.
+    pub recipient: Pubkey,
+    /// Wallet allowed to claim on the recipient's behalf.
+    pub delegate: Pubkey,
+}
+
+/// Emitted when the sender or receiver disputes an escrow.
+#[event]
+pub struct DisputeOpened {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Sender or receiver that opened the dispute.
+    pub disputant: Pubkey,
+    /// Platform-defined reason code of the dispute.
+    pub reason_code: u8,
+}
+
+/// Emitted when the moderator dismisses a dispute, returning the escrow to Pending.
+#[event]
+pub struct DisputeResolved {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Moderator that resolved the dispute.
+    pub moderator: Pubkey,
+}
+
+/// Emitted when an expired escrow is marked Expired, closing it to claims.
+#[event]
+pub struct EscrowExpired {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Unix timestamp the escrow expired at.
+    pub expires_at: i64,
+}
+
+/// Emitted when the receiver starts a two-phase release.
+#[event]
+pub struct ClaimStarted {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Receiver that started the release.
+    pub receiver: Pubkey,
+    /// Unix timestamp the release was started.
+    pub started_at: i64,
+}
+
+/// Emitted when the sender cancels a stale two-phase release, returning the escrow to Pending.
+#[event]
+pub struct ClaimCancelled {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Receiver whose release was cancelled.
+    pub receiver: Pubkey,
+}
+
+/// Emitted when the platform moderator cancels an escrow.
+#[event]
+pub struct EscrowModerated {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Moderator that cancelled the escrow.
+    pub moderator: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Escrowed lamports refunded to the sender, or their payer or beneficiary.
+    pub amount: u64,
+    /// Platform-defined moderation reason.
+    pub reason_code: u8,
+}
+
+/// Emitted when the sender schedules, or clears, the automation refunding an escrow.
+#[event]
+pub struct RefundScheduled {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Automation now allowed to crank the refund (default = any cranker).
+    pub refund_automation: Pubkey,
+}
+
+/// Emitted when the sender chooses where an unclaimed escrow is refunded.
+#[event]
+pub struct RefundBeneficiarySet {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Wallet refunds are now paid to.
+    pub refund_beneficiary: Pubkey,
+}
+
+/// Emitted when the sender extends an escrow's expiry.
+#[event]
+pub struct EscrowExtended {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// New unix timestamp after which the sender can refund.
+    pub expires_at: i64,
+}
+
+/// Emitted when an escrow is upgraded to the current layout.
+#[event]
+pub struct EscrowUpgraded {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Layout version after the upgrade.
+    pub version: u8,
+}
+
+/// Emitted when an expired escrow is refunded to its sender.
+#[event]
+pub struct EscrowRefunded {
+    /// Escrow PDA.
+    pub escrow: Pubkey,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports returned to the escrow's `refund_recipient()`.
+    pub amount: u64,
+    /// Wallet that received the refund: the payer, or the refund beneficiary the
+    /// self-funding sender chose.
+    pub beneficiary: Pubkey,
+}
+
+/// Emitted when a compressed escrow is appended, carrying the full leaf preimage.
+#[event]
+pub struct CompressedEscrowAppended {
+    /// The sender's compressed escrow tree.
+    pub tree: Pubkey,
+    /// Leaf index of the escrow in the tree.
+    pub index: u32,
+    /// Wallet that funded the escrow.
+    pub sender: Pubkey,
+    /// Wallet entitled to claim the escrow.
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports escrowed.
+    pub amount: u64,
+    /// Unix timestamp after which the sender can refund.
+    pub expires_at: i64,
+}
+
+/// Emitted when a compressed escrow is claimed or refunded and its leaf cleared.
+#[event]
+pub struct CompressedEscrowSettled {
+    /// The sender's compressed escrow tree.
+    pub tree: Pubkey,
+    /// Leaf index of the settled escrow.
+    pub index: u32,
+    /// Wallet that received the lamports (receiver on claim, sender on refund).
+    pub recipient: Pubkey,
+}
+
+/// Custom error codes for the escrow program.
+#[error_code]
+pub enum EscrowError {
+    #[msg("Escrow is not in a valid status for this operation")]
     InvalidStatus,
     #[msg("Thread ID does not match the escrow")]
     ThreadIdMismatch,
     #[msg("Sender does not match the escrow")]
     SenderMismatch,
-    #[msg("Escrow has not expired yet (15 days required)")]
+    #[msg("Escrow has not expired yet")]
     NotExpired,
     #[msg("Insufficient funds in escrow")]
     InsufficientFunds,
+    #[msg("Receiver does not match the escrow")]
+    ReceiverMismatch,
+    #[msg("No new reputation milestone has been reached")]
+    NoNewBadgeMilestone,
+    #[msg("Merkle proof does not match the escrow tree")]
+    InvalidProof,
+    #[msg("Cranker is not the scheduled refund automation")]
+    UnauthorizedCranker,
+    #[msg("Price feed is not a verified Pyth SOL/USD update")]
+    InvalidPriceFeed,
+    #[msg("Price feed is older than the allowed staleness bound")]
+    StalePrice,
+    #[msg("Escrow TTL is outside the protocol limits")]
+    InvalidTtl,
+    #[msg("Campaign has no room for more entries")]
+    CampaignFull,
+    #[msg("Campaign entry index is out of range")]
+    InvalidCampaignEntry,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Signer is not authorized for this operation")]
+    Unauthorized,
+    #[msg("Claim is not attested by the platform")]
+    InvalidAttestation,
+    #[msg("Auction slot count is outside the allowed range")]
+    InvalidAuctionSlots,
+    #[msg("Auction is closed for this operation")]
+    AuctionClosed,
+    #[msg("Auction has not closed yet")]
+    AuctionOpen,
+    #[msg("Bid did not win a slot")]
+    BidNotWinning,
+    #[msg("Reply deadline has passed")]
+    DeadlinePassed,
+    #[msg("Group escrow has no room for more participants")]
+    TooManyParticipants,
+    #[msg("Accounts do not match the group escrow participants")]
+    ParticipantMismatch,
+    #[msg("Signer is neither the receiver nor their claim delegate")]
+    UnauthorizedDelegate,
+    #[msg("Reply deadline or late haircut is out of range")]
+    InvalidReplyTerms,
+    #[msg("Early-reply bonus is already set or its window is invalid")]
+    InvalidBonus,
+    #[msg("Escrow metadata has already been committed")]
+    MetadataAlreadyCommitted,
+    #[msg("Reply has not been verified against the sender's predicate")]
+    ReplyNotVerified,
+    #[msg("Preimage does not match the reply predicate commitment")]
+    InvalidReplyPreimage,
+    #[msg("Reply quality score has not been attested")]
+    QualityNotAttested,
+    #[msg("Refund beneficiary account does not match the escrow")]
+    InvalidBeneficiary,
+    #[msg("Escrow is addressed to an email that has not been bound to a wallet")]
+    RecipientNotBound,
+    #[msg("Escrow amount is outside the protocol limits")]
+    AmountOutOfRange,
+    #[msg("Minimum escrow amount exceeds the maximum")]
+    InvalidAmountLimits,
+    #[msg("Batch accounts are missing or not grouped per escrow")]
+    BatchAccountsMismatch,
+    #[msg("An escrow with different terms already exists for this thread")]
+    EscrowAlreadyExists,
+    #[msg("Escrow has already been upgraded to the current layout")]
+    AlreadyUpgraded,
+    #[msg("Settled escrow is still within its retention window")]
+    RetentionNotElapsed,
+    #[msg("Group reply weight must be positive")]
+    InvalidReplyWeight,
+    #[msg("Escrow has expired and can no longer be claimed")]
+    AlreadyExpired,
+    #[msg("Started claim is still within its claim window")]
+    ClaimNotStale,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+    const TTL: i64 = 7 * 24 * 60 * 60;
+
+    /// Conditions with a reply predicate and a quality bar.
+    fn conditions() -> ClaimConditions {
+        ClaimConditions {
+            reply_commitment: [9; 32],
+            quality_bar: 60,
+            low_quality_payout_bps: 5_000,
+        }
+    }
+
+    /// A pending escrow opened at `NOW` with the terms the tests retry.
+    fn opened_escrow() -> Escrow {
+        let mut escrow = Escrow::deserialize(&mut &[0u8; Escrow::LEN][..]).unwrap();
+        escrow
+            .open(
+                Pubkey::new_from_array([1; 32]),
+                Pubkey::new_from_array([2; 32]),
+                [3; 32],
+                5_000_000,
+                NOW,
+                TTL,
+                254,
+            )
+            .unwrap();
+        escrow.set_reply_terms(86_400, 2_500).unwrap();
+        escrow.set_slot_expiry(250_000_000, 1_512_000).unwrap();
+        escrow.set_claim_conditions(&conditions()).unwrap();
+        escrow
+    }
+
+    #[test]
+    fn retry_with_the_same_terms_matches() {
+        let escrow = opened_escrow();
+
+        assert!(escrow
+            .opened_with(escrow.receiver, TTL, 86_400, 2_500, 1_512_000, &conditions())
+            .unwrap());
+    }
+
+    #[test]
+    fn retry_with_any_other_term_does_not_match() {
+        let escrow = opened_escrow();
+        let receiver = escrow.receiver;
+        let other_conditions = ClaimConditions {
+            quality_bar: 0,
+            ..conditions()
+        };
+
+        for matches in [
+            escrow.opened_with(Pubkey::default(), TTL, 86_400, 2_500, 1_512_000, &conditions()),
+            escrow.opened_with(receiver, TTL + 1, 86_400, 2_500, 1_512_000, &conditions()),
+            escrow.opened_with(receiver, TTL, 0, 2_500, 1_512_000, &conditions()),
+            escrow.opened_with(receiver, TTL, 86_400, 0, 1_512_000, &conditions()),
+            escrow.opened_with(receiver, TTL, 86_400, 2_500, 0, &conditions()),
+            escrow.opened_with(receiver, TTL, 86_400, 2_500, 1_512_000, &other_conditions),
+        ] {
+            assert!(!matches.unwrap());
+        }
+    }
+
+    #[test]
+    fn refunds_go_to_the_payer_unless_a_beneficiary_was_chosen() {
+        let mut escrow = opened_escrow();
+        assert_eq!(escrow.refund_recipient(), escrow.sender);
+
+        escrow.payer = Pubkey::new_from_array([5; 32]);
+        assert_eq!(escrow.refund_recipient(), escrow.payer);
+
+        escrow.refund_beneficiary = Pubkey::new_from_array([6; 32]);
+        assert_eq!(escrow.refund_recipient(), escrow.refund_beneficiary);
+    }
+
+    #[test]
+    fn self_funded_replies_are_not_counted() {
+        let mut stats = RecipientStats::deserialize(&mut &[0u8; RecipientStats::LEN][..]).unwrap();
+        let mut escrow = opened_escrow();
+        let receiver = escrow.receiver;
+
+        stats.record_reply(receiver, &escrow, NOW + 3_600, 255).unwrap();
+        assert_eq!(stats.reply_count, 1);
+        assert_eq!(stats.total_response_time, 3_600);
+
+        escrow.payer = receiver;
+        stats.record_reply(receiver, &escrow, NOW + 3_600, 255).unwrap();
+        escrow.payer = escrow.sender;
+        escrow.sender = receiver;
+        stats.record_reply(receiver, &escrow, NOW + 3_600, 255).unwrap();
+        assert_eq!(stats.reply_count, 1);
+        assert_eq!(stats.recipient, receiver);
+        assert_eq!(stats.bump, 255);
+    }
+
+    #[test]
+    fn expiry_accepts_only_ttls_within_the_limits() {
+        assert_eq!(expiry(NOW, MIN_TTL_SECONDS).unwrap(), NOW + MIN_TTL_SECONDS);
+        assert_eq!(expiry(NOW, MAX_TTL_SECONDS).unwrap(), NOW + MAX_TTL_SECONDS);
+
+        for ttl_seconds in [0, -1, MIN_TTL_SECONDS - 1, MAX_TTL_SECONDS + 1, i64::MAX] {
+            assert_eq!(
+                expiry(NOW, ttl_seconds).unwrap_err(),
+                EscrowError::InvalidTtl.into()
+            );
+        }
+        assert_eq!(
+            expiry(i64::MAX, MIN_TTL_SECONDS).unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+    }
+
+    #[test]
+    fn slot_expiry_accepts_only_ttls_within_the_limits() {
+        let min_slots = MIN_TTL_SECONDS as u64 * 1_000 / SLOT_DURATION_MS;
+        let max_slots = MAX_TTL_SECONDS as u64 * 1_000 / SLOT_DURATION_MS;
+
+        let mut escrow = opened_escrow();
+        escrow.expiry_slot = 0;
+        escrow.ttl_slots = 0;
+        escrow.set_slot_expiry(u64::MAX, 0).unwrap();
+        assert_eq!(escrow.expiry_slot, 0);
+
+        escrow.set_slot_expiry(1_000, max_slots).unwrap();
+        assert_eq!(escrow.expiry_slot, 1_000 + max_slots);
+        assert_eq!(escrow.ttl_slots, max_slots);
+
+        for ttl_slots in [1, min_slots - 1, max_slots + 1, u64::MAX] {
+            assert_eq!(
+                escrow.set_slot_expiry(1_000, ttl_slots).unwrap_err(),
+                EscrowError::InvalidTtl.into()
+            );
+        }
+        assert_eq!(
+            escrow.set_slot_expiry(u64::MAX, min_slots).unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+        assert_eq!(escrow.ttl_slots, max_slots);
+    }
+
+    #[test]
+    fn pending_amount_rejects_overflowing_bonuses() {
+        let mut escrow = opened_escrow();
+        escrow.amount = u64::MAX;
+        assert_eq!(escrow.pending_amount().unwrap(), u64::MAX);
+
+        escrow.bonus = 1;
+        assert_eq!(
+            escrow.pending_amount().unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+
+        escrow.amount = 1;
+        escrow.bonus = u64::MAX;
+        assert_eq!(
+            escrow.pending_amount().unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+    }
+
+    #[test]
+    fn payout_splits_do_not_overflow_at_u64_max() {
+        let mut escrow = opened_escrow();
+        let late = escrow.reply_deadline + 1;
+        escrow.late_haircut_bps = BPS_DENOMINATOR as u16;
+        escrow.quality_score = 0;
+        escrow.low_quality_payout_bps = 0;
+
+        assert_eq!(escrow.late_haircut(u64::MAX, late).unwrap(), u64::MAX);
+        assert_eq!(escrow.late_haircut(u64::MAX, escrow.reply_deadline).unwrap(), 0);
+        assert_eq!(escrow.quality_slash(u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(escrow.refund_share(u64::MAX, late).unwrap(), u64::MAX);
+        assert_eq!(escrow.forfeited_bonus(late), 0);
+
+        // A bonus worth the whole payout is forfeited in full once its window passes.
+        escrow.bonus = u64::MAX;
+        escrow.bonus_deadline = NOW;
+        assert_eq!(escrow.refund_share(u64::MAX, late).unwrap(), 0);
+        assert_eq!(escrow.forfeited_bonus(late), u64::MAX);
+        assert_eq!(escrow.forfeited_bonus(NOW), 0);
+        assert_eq!(
+            escrow.refund_share(u64::MAX - 1, NOW).unwrap_err(),
+            EscrowError::InsufficientFunds.into()
+        );
+    }
+
+    #[test]
+    fn reply_terms_and_extensions_reject_overflowing_timestamps() {
+        let mut escrow = opened_escrow();
+        escrow.created_at = i64::MAX;
+        assert_eq!(
+            escrow.set_reply_terms(1, 0).unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+
+        let mut escrow = opened_escrow();
+        escrow.expires_at = i64::MAX;
+        assert_eq!(
+            escrow.extend(1).unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+
+        let mut escrow = opened_escrow();
+        escrow.expiry_slot = u64::MAX;
+        assert_eq!(
+            escrow.extend(MIN_TTL_SECONDS).unwrap_err(),
+            EscrowError::ArithmeticOverflow.into()
+        );
+        assert_eq!(escrow.expires_at, opened_escrow().expires_at);
+    }
+
+    #[test]
+    fn settled_escrows_never_match_a_retry() {
+        let mut escrow = opened_escrow();
+        escrow.status = EscrowStatus::Refunded;
+
+        assert!(!escrow
+            .opened_with(escrow.receiver, TTL, 86_400, 2_500, 1_512_000, &conditions())
+            .unwrap());
+    }
+
+    #[test]
+    fn only_started_releases_are_claimable_after_expiry() {
+        let mut escrow = opened_escrow();
+        let before = Clock {
+            slot: escrow.expiry_slot - 1,
+            ..Clock::default()
+        };
+        let after = Clock {
+            slot: escrow.expiry_slot,
+            ..Clock::default()
+        };
+
+        assert!(escrow.check_claimable(&before).is_ok());
+        assert_eq!(
+            escrow.check_claimable(&after).unwrap_err(),
+            EscrowError::AlreadyExpired.into()
+        );
+
+        escrow.status.transition_to(EscrowStatus::Claiming).unwrap();
+        assert!(escrow.check_claimable(&after).is_ok());
+
+        escrow.status = EscrowStatus::Expired;
+        assert_eq!(
+            escrow.check_claimable(&before).unwrap_err(),
+            EscrowError::InvalidStatus.into()
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Pooled bounties funded by several senders.
+//!
+//! A community can collectively pay an expert to respond: anyone contributes to
+//! the same `BountyPool`, each contribution is tracked in its own PDA, and if the
+//! receiver never claims, every contributor gets their own share back after expiry.
+//! Once the last contribution is settled the pool is closed and its rent returned
+//! to the creator.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::{close_account, expiry, transfer_lamports, EscrowError, EscrowStatus, PlatformConfig};
+
+/// Bounty for one email thread funded by any number of contributors.
+#[account]
+#[derive(Debug)]
+pub struct BountyPool {
+    /// Wallet that opened the pool.
+    pub creator: Pubkey,
+    /// Wallet allowed to claim the pooled funds.
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports currently pooled.
+    pub total: u64,
+    /// Number of contributors whose contribution has not been settled yet.
+    pub contributor_count: u32,
+    /// Unix timestamp after which contributors can be refunded.
+    pub expires_at: i64,
+    /// Current status of the pool.
+    pub status: EscrowStatus,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl BountyPool {
+    /// Size of the BountyPool account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // creator
+        32 + // receiver
+        32 + // thread_id
+        8 + // total
+        4 + // contributor_count
+        8 + // expires_at
+        1 + // status
+        1; // bump
+}
+
+/// One contributor's share of a bounty pool.
+#[account]
+#[derive(Debug)]
+pub struct PoolContribution {
+    /// Pool the contribution belongs to.
+    pub pool: Pubkey,
+    /// Wallet that contributed.
+    pub contributor: Pubkey,
+    /// Lamports contributed.
+    pub amount: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl PoolContribution {
+    /// Size of the PoolContribution account (excluding the 8-byte Anchor discriminator).
+    pub const LEN: usize =
+        32 + // pool
+        32 + // contributor
+        8 + // amount
+        1; // bump
+}
+
+/// Accounts required to open a bounty pool.
+#[derive(Accounts)]
+#[instruction(thread_id: [u8; 32])]
+pub struct CreateBountyPool<'info> {
+    /// The wallet opening the pool.
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// PDA holding the pooled lamports and state.
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + BountyPool::LEN,
+        seeds = [b"bounty_pool", creator.key().as_ref(), &thread_id],
+        bump,
+    )]
+    pub pool: Account<'info, BountyPool>,
+
+    /// System program for creating the account.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to contribute to a bounty pool.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ContributeToPool<'info> {
+    /// The contributor funding the pool.
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    /// The pool receiving the lamports.
+    #[account(mut)]
+    pub pool: Account<'info, BountyPool>,
+
+    /// PDA tracking the contributor's share, created on their first contribution.
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + PoolContribution::LEN,
+        seeds = [b"pool_contribution", pool.key().as_ref(), contributor.key().as_ref()],
+        bump,
+    )]
+    pub contribution: Account<'info, PoolContribution>,
+
+    /// System program for creating the account and transferring lamports.
+    pub system_program: Program<'info, System>,
+
+    /// Platform config bounding escrow amounts.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Accounts required for the receiver to claim a bounty pool.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimBountyPool<'info> {
+    /// The receiver claiming the pooled funds.
+    #[account(mut)]
+    pub receiver: Signer<'info>,
+
+    /// The pool being claimed.
+    #[account(mut, has_one = receiver @ EscrowError::ReceiverMismatch)]
+    pub pool: Account<'info, BountyPool>,
+}
+
+/// Accounts required to settle a contribution once its pool is claimed or expired.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettlePoolContribution<'info> {
+    /// Anyone may settle a contribution once it is due.
+    pub cranker: Signer<'info>,
+
+    /// The pool the contribution funded, closed to its creator with the last contribution.
+    #[account(mut, has_one = creator)]
+    pub pool: Account<'info, BountyPool>,
+
+    /// The contribution, closed to the contributor.
+    #[account(
+        mut,
+        seeds = [b"pool_contribution", pool.key().as_ref(), contributor.key().as_ref()],
+        bump = contribution.bump,
+        has_one = pool,
+        has_one = contributor,
+        close = contributor,
+    )]
+    pub contribution: Account<'info, PoolContribution>,
+
+    /// The contributor receiving the rent and, for unclaimed pools, their refund.
+    #[account(mut)]
+    pub contributor: SystemAccount<'info>,
+
+    /// The wallet that opened the pool, receiving its rent once every contribution is settled.
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+}
+
+/// Emitted when a contributor adds lamports to a bounty pool.
+#[event]
+pub struct PoolContributed {
+    /// Pool receiving the contribution.
+    pub pool: Pubkey,
+    /// Wallet that contributed.
+    pub contributor: Pubkey,
+    /// Lamports contributed.
+    pub amount: u64,
+    /// Lamports pooled after the contribution.
+    pub total: u64,
+}
+
+/// Emitted when the receiver claims a bounty pool.
+#[event]
+pub struct BountyPoolClaimed {
+    /// The claimed pool.
+    pub pool: Pubkey,
+    /// Wallet that claimed the pool.
+    pub receiver: Pubkey,
+    /// Deterministic identifier for the email thread.
+    pub thread_id: [u8; 32],
+    /// Lamports paid out to the receiver.
+    pub amount: u64,
+}
+
+/// Emitted when a contribution is closed, refunding it if the pool went unclaimed.
+#[event]
+pub struct PoolContributionSettled {
+    /// Pool the contribution funded.
+    pub pool: Pubkey,
+    /// Wallet that contributed.
+    pub contributor: Pubkey,
+    /// Lamports refunded to the contributor (0 if the pool was claimed).
+    pub refunded: u64,
+    /// Whether this was the last contribution, closing the pool.
+    pub pool_closed: bool,
+}
+
+pub fn process_create_bounty_pool(
+    ctx: Context<CreateBountyPool>,
+    thread_id: [u8; 32],
+    receiver: Pubkey,
+    ttl_seconds: i64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let clock = Clock::get()?;
+
+    pool.creator = ctx.accounts.creator.key();
+    pool.receiver = receiver;
+    pool.thread_id = thread_id;
+    pool.total = 0;
+    pool.contributor_count = 0;
+    pool.expires_at = expiry(clock.unix_timestamp, ttl_seconds)?;
+    pool.status = EscrowStatus::Pending;
+    pool.bump = ctx.bumps.pool;
+
+    Ok(())
+}
+
+pub fn process_contribute_to_pool(ctx: Context<ContributeToPool>, amount: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let contribution = &mut ctx.accounts.contribution;
+    let clock = Clock::get()?;
+
+    // Verify the pool is still open for contributions.
+    require!(
+        pool.status == EscrowStatus::Pending,
+        EscrowError::InvalidStatus
+    );
+    require!(
+        clock.unix_timestamp < pool.expires_at,
+        EscrowError::DeadlinePassed
+    );
+
+    // Verify the contribution is within the protocol limits.
+    ctx.accounts.config.check_escrow_amount(amount)?;
+
+    // Record the share, counting the contributor on their first contribution.
+    if contribution.contributor == Pubkey::default() {
+        contribution.pool = pool.key();
+        contribution.contributor = ctx.accounts.contributor.key();
+        contribution.bump = ctx.bumps.contribution;
+        pool.contributor_count = pool
+            .contributor_count
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+    }
+    contribution.amount = contribution
+        .amount
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    pool.total = pool
+        .total
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    // Transfer lamports from the contributor to the pool PDA.
+    let ix = system_instruction::transfer(&ctx.accounts.contributor.key(), &pool.key(), amount);
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.contributor.to_account_info(),
+            ctx.accounts.pool.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    emit_cpi!(PoolContributed {
+        pool: ctx.accounts.pool.key(),
+        contributor: ctx.accounts.contributor.key(),
+        amount,
+        total: ctx.accounts.pool.total,
+    });
+
+    Ok(())
+}
+
+pub fn process_claim_bounty_pool(ctx: Context<ClaimBountyPool>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+
+    // Verify the pool is in Pending status.
+    require!(
+        pool.status == EscrowStatus::Pending,
+        EscrowError::InvalidStatus
+    );
+
+    // Pay out everything pooled; contributions are closed afterwards for their rent.
+    let amount = pool.total;
+    pool.status.transition_to(EscrowStatus::Completed)?;
+    transfer_lamports(
+        &ctx.accounts.pool.to_account_info(),
+        &ctx.accounts.receiver.to_account_info(),
+        amount,
+    )?;
+
+    emit_cpi!(BountyPoolClaimed {
+        pool: ctx.accounts.pool.key(),
+        receiver: ctx.accounts.receiver.key(),
+        thread_id: ctx.accounts.pool.thread_id,
+        amount,
+    });
+
+    Ok(())
+}
+
+pub fn process_settle_pool_contribution(ctx: Context<SettlePoolContribution>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let contribution = &ctx.accounts.contribution;
+    let clock = Clock::get()?;
+
+    // Claimed pools only release rent; unclaimed ones refund each share after expiry.
+    let refunded = if pool.status == EscrowStatus::Completed {
+        0
+    } else {
+        require!(
+            clock.unix_timestamp >= pool.expires_at,
+            EscrowError::NotExpired
+        );
+        // The first refunded contribution marks the whole pool as refunded.
+        if pool.status == EscrowStatus::Pending {
+            pool.status.transition_to(EscrowStatus::Refunded)?;
+        }
+        pool.total = pool
+            .total
+            .checked_sub(contribution.amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        transfer_lamports(
+            &pool.to_account_info(),
+            &ctx.accounts.contributor.to_account_info(),
+            contribution.amount,
+        )?;
+        contribution.amount
+    };
+
+    pool.contributor_count = pool
+        .contributor_count
+        .checked_sub(1)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    // The last settled contribution closes the pool, returning its rent to the creator.
+    let pool_closed = pool.contributor_count == 0;
+    if pool_closed {
+        close_account(
+            &pool.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+        )?;
+    }
+
+    // The `close` constraint returns the contribution's rent to the contributor.
+    emit_cpi!(PoolContributionSettled {
+        pool: ctx.accounts.pool.key(),
+        contributor: contribution.contributor,
+        refunded,
+        pool_closed,
+    });
+
+    Ok(())
+}
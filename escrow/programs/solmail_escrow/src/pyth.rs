@@ -0,0 +1,107 @@
+//! Minimal reader for Pyth pull-oracle `PriceUpdateV2` accounts.
+//!
+//! Only the fields needed to price USD-denominated escrows are decoded, which
+//! avoids pulling the Pyth SDK and its Solana version pins into the program.
+
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+/// Pyth Solana receiver program owning `PriceUpdateV2` accounts.
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Pyth SOL/USD price feed id.
+pub const SOL_USD_FEED_ID: [u8; 32] = [
+    0xef, 0x0d, 0x8b, 0x6f, 0xda, 0x2c, 0xeb, 0xa4, 0x1d, 0xa1, 0x5d, 0x40, 0x95, 0xd1, 0xda, 0x39,
+    0x2a, 0x0d, 0x2f, 0x8e, 0xd0, 0xc6, 0xc7, 0xbc, 0x0f, 0x4c, 0xfa, 0xc8, 0xc2, 0x80, 0xb5, 0x6d,
+];
+
+/// Maximum age in seconds of a price accepted by the program.
+pub const MAX_PRICE_AGE: i64 = 60;
+
+/// Anchor discriminator of `PriceUpdateV2` accounts.
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[derive(AnchorDeserialize)]
+enum VerificationLevel {
+    Partial {
+        #[allow(dead_code)]
+        num_signatures: u8,
+    },
+    Full,
+}
+
+#[derive(AnchorDeserialize)]
+struct PriceFeedMessage {
+    feed_id: [u8; 32],
+    price: i64,
+    _conf: u64,
+    exponent: i32,
+    publish_time: i64,
+}
+
+#[derive(AnchorDeserialize)]
+struct PriceUpdateV2 {
+    _write_authority: Pubkey,
+    verification_level: VerificationLevel,
+    price_message: PriceFeedMessage,
+}
+
+/// A verified SOL/USD price: `price * 10^exponent` USD per SOL.
+pub struct SolUsdPrice {
+    pub price: i64,
+    pub exponent: i32,
+}
+
+/// Read a fully verified SOL/USD price no older than `MAX_PRICE_AGE` seconds.
+pub fn read_sol_usd_price(price_update: &AccountInfo, now: i64) -> Result<SolUsdPrice> {
+    require_keys_eq!(
+        *price_update.owner,
+        PYTH_RECEIVER_ID,
+        EscrowError::InvalidPriceFeed
+    );
+
+    let data = price_update.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+        EscrowError::InvalidPriceFeed
+    );
+    let update = PriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| error!(EscrowError::InvalidPriceFeed))?;
+    let message = update.price_message;
+
+    require!(
+        matches!(update.verification_level, VerificationLevel::Full)
+            && message.feed_id == SOL_USD_FEED_ID
+            && message.price > 0,
+        EscrowError::InvalidPriceFeed
+    );
+    let age = now
+        .checked_sub(message.publish_time)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    require!(age <= MAX_PRICE_AGE, EscrowError::StalePrice);
+
+    Ok(SolUsdPrice {
+        price: message.price,
+        exponent: message.exponent,
+    })
+}
+
+/// Convert a USD amount in cents to lamports at the given price.
+pub fn usd_cents_to_lamports(usd_cents: u64, sol_usd: &SolUsdPrice) -> Result<u64> {
+    // lamports = cents / 100 * 10^9 / (price * 10^exponent)
+    //          = cents * 10^(7 - exponent) / price
+    let power = 7i32
+        .checked_sub(sol_usd.exponent)
+        .and_then(|power| u32::try_from(power).ok())
+        .ok_or(EscrowError::InvalidPriceFeed)?;
+    let scale = 10u128
+        .checked_pow(power)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    let lamports = (usd_cents as u128)
+        .checked_mul(scale)
+        .and_then(|value| value.checked_div(sol_usd.price as u128))
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    u64::try_from(lamports).map_err(|_| error!(EscrowError::ArithmeticOverflow))
+}
@@ -0,0 +1,27 @@
+//! Escrow lifecycle state.
+//!
+//! Every status change of an escrow goes through [`EscrowStatus::transition_to`],
+//! so the legal lifecycle is defined in one place, the `transitions` module.
+
+use anchor_lang::prelude::*;
+
+pub mod transitions;
+
+/// Lifecycle status of an escrow.
+///
+/// New variants are only ever appended, keeping the encoding of stored escrows stable.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum EscrowStatus {
+    /// Funded and awaiting a reply or expiry.
+    Pending,
+    /// Paid out to the receiver.
+    Completed,
+    /// Returned to the payer or the refund beneficiary.
+    Refunded,
+    /// Frozen by the sender or receiver until the moderator resolves the dispute.
+    Disputed,
+    /// Past expiry and awaiting its refund; it can no longer be claimed.
+    Expired,
+    /// Release started by the receiver and awaiting its second phase, the claim.
+    Claiming,
+}
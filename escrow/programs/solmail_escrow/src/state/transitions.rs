@@ -0,0 +1,116 @@
+//! Legal escrow status transitions.
+//!
+//! | From      | To                                               |
+//! |-----------|--------------------------------------------------|
+//! | Pending   | Completed, Refunded, Disputed, Expired, Claiming |
+//! | Claiming  | Completed, Pending, Disputed                     |
+//! | Disputed  | Pending, Refunded                                |
+//! | Expired   | Refunded                                         |
+//! | Completed | (terminal)                                       |
+//! | Refunded  | (terminal)                                       |
+//!
+//! Every transition is performed by an instruction:
+//! - Pending → Completed: `register_and_claim`, `release_many`.
+//! - Pending → Refunded: the refund instructions and `moderator_cancel`.
+//! - Pending or Claiming → Disputed: `open_dispute`.
+//! - Pending → Expired: `expire_escrow`.
+//! - Pending → Claiming: `begin_claim`.
+//! - Claiming → Completed: `register_and_claim`, `release_many`.
+//! - Claiming → Pending: `cancel_stale_claim`.
+//! - Disputed → Pending: `resolve_dispute`.
+//! - Disputed → Refunded: `moderator_cancel`.
+//! - Expired → Refunded: the refund instructions.
+
+use anchor_lang::prelude::*;
+
+use super::EscrowStatus;
+use crate::EscrowError;
+
+impl EscrowStatus {
+    /// Whether an escrow in this status may move to `next`.
+    pub fn can_transition_to(&self, next: &EscrowStatus) -> bool {
+        use EscrowStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending, Completed | Refunded | Disputed | Expired | Claiming)
+                | (Claiming, Completed | Pending | Disputed)
+                | (Disputed, Pending | Refunded)
+                | (Expired, Refunded)
+        )
+    }
+
+    /// Move to `next`, rejecting transitions the lifecycle does not allow.
+    pub fn transition_to(&mut self, next: EscrowStatus) -> Result<()> {
+        require!(
+            self.can_transition_to(&next),
+            EscrowError::InvalidStatus
+        );
+        *self = next;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EscrowStatus::*;
+
+    const ALL_STATUSES: [EscrowStatus; 6] =
+        [Pending, Completed, Refunded, Disputed, Expired, Claiming];
+
+    /// The table in the module docs, written out pair by pair.
+    fn expected(from: &EscrowStatus, to: &EscrowStatus) -> bool {
+        match (from, to) {
+            (Pending, Pending) => false,
+            (Pending, Completed) => true,
+            (Pending, Refunded) => true,
+            (Pending, Disputed) => true,
+            (Pending, Expired) => true,
+            (Pending, Claiming) => true,
+            (Completed, _) => false,
+            (Refunded, _) => false,
+            (Disputed, Pending) => true,
+            (Disputed, Refunded) => true,
+            (Disputed, _) => false,
+            (Expired, Refunded) => true,
+            (Expired, _) => false,
+            (Claiming, Completed) => true,
+            (Claiming, Pending) => true,
+            (Claiming, Disputed) => true,
+            (Claiming, _) => false,
+        }
+    }
+
+    #[test]
+    fn every_pair_matches_the_table() {
+        for from in &ALL_STATUSES {
+            for to in &ALL_STATUSES {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    expected(from, to),
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn transition_to_applies_legal_moves_only() {
+        for from in &ALL_STATUSES {
+            for to in &ALL_STATUSES {
+                let mut status = from.clone();
+                let result = status.transition_to(to.clone());
+
+                if expected(from, to) {
+                    assert!(result.is_ok(), "{from:?} -> {to:?}");
+                    assert_eq!(&status, to);
+                } else {
+                    assert_eq!(result.unwrap_err(), EscrowError::InvalidStatus.into());
+                    assert_eq!(&status, from, "rejected {from:?} -> {to:?} changed status");
+                }
+            }
+        }
+    }
+}
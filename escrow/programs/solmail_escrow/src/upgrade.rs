@@ -0,0 +1,225 @@
+//! Migration of escrows created with an older account layout.
+//!
+//! Fields are only ever appended to `Escrow`, yet an account written with the
+//! baseline layout still cannot be read with the current one. `migrate_escrow`
+//! decodes that layout explicitly and maps its fields onto the current `Escrow`,
+//! filling the fields it lacked with the defaults a freshly opened escrow gets.
+
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError, EscrowStatus, Inbox, Thread, ESCROW_VERSION};
+
+/// Escrow as first deployed (v1), before the `version` field existed.
+#[derive(AnchorDeserialize)]
+struct EscrowV1 {
+    sender: Pubkey,
+    receiver: Pubkey,
+    thread_id: [u8; 32],
+    amount: u64,
+    created_at: i64,
+    expires_at: i64,
+    status: EscrowStatus,
+    bump: u8,
+}
+
+impl EscrowV1 {
+    /// Map a v1 escrow onto the current layout.
+    fn migrate(self) -> Escrow {
+        Escrow {
+            sender: self.sender,
+            receiver: self.receiver,
+            thread_id: self.thread_id,
+            amount: self.amount,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            status: self.status,
+            bump: self.bump,
+            refund_automation: Pubkey::default(), // any cranker until scheduled
+            usd_cents: 0, // denominated in lamports
+            reply_deadline: self.expires_at, // full payout until expiry
+            late_haircut_bps: 0,
+            bonus: 0,
+            bonus_deadline: 0,
+            subject_hash: [0; 32],
+            attachment_hash: [0; 32],
+            reply_commitment: [0; 32],
+            reply_verified: false,
+            claim_fee_buffer: 0,
+            quality_bar: 0,
+            low_quality_payout_bps: 0,
+            quality_score: 0,
+            quality_attested: false,
+            refund_beneficiary: Pubkey::default(), // refunds go to the sender
+            expiry_slot: 0, // expires by unix time
+            recipient_email_hash: [0; 32],
+            version: ESCROW_VERSION,
+            token_mint: Pubkey::default(), // native SOL
+            referrer: Pubkey::default(),
+            disputed_at: 0,
+            dispute_reason: 0,
+            settled_at: 0,
+            ttl_slots: 0, // expires by unix time
+            payer: self.sender, // self-funded
+            claim_started_at: 0,
+        }
+    }
+}
+
+/// Decode the data of an escrow account written with an older layout and map
+/// it onto the current one.
+///
+/// Accounts already using the current layout are rejected with `AlreadyUpgraded`.
+pub fn migrate_escrow(data: &[u8]) -> Result<Escrow> {
+    require!(
+        data.starts_with(Escrow::DISCRIMINATOR),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let mut fields = &data[Escrow::DISCRIMINATOR.len()..];
+
+    // Verify the escrow is not on the current layout yet, so it is only migrated once.
+    require!(
+        fields.len() != Escrow::LEN,
+        EscrowError::AlreadyUpgraded
+    );
+
+    // Verify the account uses the baseline layout, the only older one deployed.
+    require!(
+        fields.len() == Escrow::V1_LEN,
+        ErrorCode::AccountDidNotDeserialize
+    );
+    let escrow = EscrowV1::deserialize(&mut fields)
+        .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))?;
+
+    Ok(escrow.migrate())
+}
+
+/// Record an upgraded escrow in its `Thread` and `Inbox`, as its creation would have.
+///
+/// Settled baseline escrows were closed, so only pending ones are expected; anything
+/// else holds no pending lamports and is left out of the totals.
+pub fn record_upgraded_escrow(
+    escrow: &Escrow,
+    thread: &mut Thread,
+    thread_bump: u8,
+    inbox: &mut Inbox,
+    inbox_bump: u8,
+) -> Result<()> {
+    if escrow.status != EscrowStatus::Pending {
+        return Ok(());
+    }
+
+    // Verify the escrow is an open claim, as the inbox it is recorded in.
+    require_keys_eq!(
+        escrow.receiver,
+        Pubkey::default(),
+        EscrowError::ReceiverMismatch
+    );
+
+    let pending = escrow.pending_amount()?;
+    thread.record_escrow(escrow.thread_id, pending, thread_bump)?;
+    inbox.record_escrow(escrow.receiver, pending, escrow.expires_at, inbox_bump)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A v1 escrow account, byte for byte as the baseline program wrote it.
+    fn v1_account() -> Vec<u8> {
+        let mut data = Escrow::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[1; 32]); // sender
+        data.extend_from_slice(&[0; 32]); // receiver, only set on claim
+        data.extend_from_slice(&[3; 32]); // thread_id
+        data.extend_from_slice(&5_000_000u64.to_le_bytes()); // amount
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // created_at
+        data.extend_from_slice(&1_700_604_800i64.to_le_bytes()); // expires_at
+        data.push(0); // status: Pending
+        data.push(254); // bump
+        data
+    }
+
+    #[test]
+    fn v1_layout_is_122_bytes() {
+        assert_eq!(Escrow::V1_LEN, 122);
+        assert_eq!(v1_account().len(), 8 + Escrow::V1_LEN);
+    }
+
+    #[test]
+    fn migrates_a_v1_account() {
+        let escrow = migrate_escrow(&v1_account()).unwrap();
+
+        assert_eq!(escrow.sender, Pubkey::new_from_array([1; 32]));
+        assert_eq!(escrow.receiver, Pubkey::default());
+        assert_eq!(escrow.thread_id, [3; 32]);
+        assert_eq!(escrow.amount, 5_000_000);
+        assert_eq!(escrow.created_at, 1_700_000_000);
+        assert_eq!(escrow.expires_at, 1_700_604_800);
+        assert_eq!(escrow.status, EscrowStatus::Pending);
+        assert_eq!(escrow.bump, 254);
+        assert_eq!(escrow.reply_deadline, escrow.expires_at);
+        assert_eq!(escrow.refund_beneficiary, Pubkey::default());
+        assert_eq!(escrow.expiry_slot, 0);
+        assert_eq!(escrow.payer, escrow.sender);
+        assert_eq!(escrow.version, ESCROW_VERSION);
+
+        let mut upgraded = Vec::new();
+        escrow.try_serialize(&mut upgraded).unwrap();
+        assert_eq!(upgraded.len(), 8 + Escrow::LEN);
+        let reread = Escrow::try_deserialize(&mut &upgraded[..]).unwrap();
+        assert_eq!(reread.amount, 5_000_000);
+        assert_eq!(reread.version, ESCROW_VERSION);
+    }
+
+    #[test]
+    fn rejects_current_and_foreign_accounts() {
+        let mut current = Escrow::DISCRIMINATOR.to_vec();
+        current.resize(8 + Escrow::LEN, 0);
+        assert_eq!(
+            migrate_escrow(&current).unwrap_err(),
+            EscrowError::AlreadyUpgraded.into()
+        );
+
+        let mut foreign = v1_account();
+        foreign[0] ^= 0xff;
+        assert_eq!(
+            migrate_escrow(&foreign).unwrap_err(),
+            ErrorCode::AccountDiscriminatorMismatch.into()
+        );
+    }
+
+    #[test]
+    fn upgraded_v1_escrows_can_be_refunded() {
+        let mut escrow = migrate_escrow(&v1_account()).unwrap();
+        let mut thread = Thread::deserialize(&mut &[0u8; Thread::LEN][..]).unwrap();
+        let mut inbox = Inbox::deserialize(&mut &[0u8; Inbox::LEN][..]).unwrap();
+
+        record_upgraded_escrow(&escrow, &mut thread, 253, &mut inbox, 252).unwrap();
+        assert_eq!(thread.thread_id, escrow.thread_id);
+        assert_eq!(thread.escrow_count, 1);
+        assert_eq!(thread.total_pending, 5_000_000);
+        assert_eq!(inbox.pending_count, 1);
+        assert_eq!(inbox.total_pending, 5_000_000);
+        assert_eq!(inbox.newest_expiry, escrow.expires_at);
+
+        // Refund it as `refund_escrow` does.
+        escrow.status.transition_to(EscrowStatus::Refunded).unwrap();
+        let pending = escrow.pending_amount().unwrap();
+        thread.settle(pending, 0).unwrap();
+        inbox.settle(pending).unwrap();
+        assert_eq!(thread.total_pending, 0);
+        assert_eq!(inbox.pending_count, 0);
+        assert_eq!(inbox.total_pending, 0);
+    }
+
+    #[test]
+    fn settled_escrows_are_not_recorded_on_upgrade() {
+        let mut escrow = migrate_escrow(&v1_account()).unwrap();
+        escrow.status = EscrowStatus::Refunded;
+        let mut thread = Thread::deserialize(&mut &[0u8; Thread::LEN][..]).unwrap();
+        let mut inbox = Inbox::deserialize(&mut &[0u8; Inbox::LEN][..]).unwrap();
+
+        record_upgraded_escrow(&escrow, &mut thread, 253, &mut inbox, 252).unwrap();
+        assert_eq!(thread.escrow_count, 0);
+        assert_eq!(inbox.pending_count, 0);
+    }
+}
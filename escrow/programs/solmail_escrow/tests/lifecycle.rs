@@ -0,0 +1,311 @@
+//! Property-based model of the escrow lifecycle.
+//!
+//! Random operation sequences are driven through a real `Escrow`: statuses move
+//! with `EscrowStatus::transition_to`, expiries with `Escrow::extend`, and claims
+//! are split with `Escrow::refund_share` and `Escrow::forfeited_bonus`, against a
+//! lamport ledger for the sender (who also funds the escrow), receiver and escrow.
+//! Funds must be conserved, settle at most once, and never get stuck.
+
+use anchor_lang::AnchorDeserialize;
+use proptest::prelude::*;
+use solmail_escrow::{Escrow, EscrowError, EscrowStatus};
+
+const ALL_STATUSES: [EscrowStatus; 6] = [
+    EscrowStatus::Pending,
+    EscrowStatus::Completed,
+    EscrowStatus::Refunded,
+    EscrowStatus::Disputed,
+    EscrowStatus::Expired,
+    EscrowStatus::Claiming,
+];
+
+/// Unix timestamp the modelled escrows are created at.
+const NOW: i64 = 1_700_000_000;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// Creation terms of the modelled escrow.
+#[derive(Clone, Debug)]
+struct Terms {
+    amount: u64,
+    bonus: u64,
+    ttl: i64,
+    reply_window: i64,
+    late_haircut_bps: u16,
+    bonus_window: i64,
+    quality_bar: u8,
+    low_quality_payout_bps: u16,
+    quality_score: u8,
+}
+
+fn terms() -> impl Strategy<Value = Terms> {
+    (
+        1..=u64::MAX / 4,
+        prop_oneof![Just(0), 1..=u64::MAX / 4],
+        (DAY..=90 * DAY).prop_flat_map(|ttl| (Just(ttl), 0..=ttl)),
+        0..=10_000u16,
+        0..=90 * DAY,
+        (prop_oneof![Just(0), 1..=100u8], 0..=10_000u16, 0..=100u8),
+    )
+        .prop_map(
+            |(amount, bonus, (ttl, reply_window), late_haircut_bps, bonus_window, quality)| {
+                let (quality_bar, low_quality_payout_bps, quality_score) = quality;
+                Terms {
+                    amount,
+                    bonus,
+                    ttl,
+                    reply_window,
+                    late_haircut_bps,
+                    bonus_window,
+                    quality_bar,
+                    low_quality_payout_bps,
+                    quality_score,
+                }
+            },
+        )
+}
+
+/// A pending escrow opened at `NOW` with `terms`.
+fn open_escrow(terms: &Terms) -> Escrow {
+    let mut escrow = Escrow::deserialize(&mut &[0u8; Escrow::LEN][..]).unwrap();
+    escrow.amount = terms.amount;
+    escrow.created_at = NOW;
+    escrow.expires_at = NOW + terms.ttl;
+    escrow.status = EscrowStatus::Pending;
+    escrow.reply_deadline = NOW + terms.reply_window;
+    escrow.late_haircut_bps = terms.late_haircut_bps;
+    escrow.bonus = terms.bonus;
+    escrow.bonus_deadline = NOW + terms.bonus_window;
+    escrow.quality_bar = terms.quality_bar;
+    escrow.low_quality_payout_bps = terms.low_quality_payout_bps;
+    escrow.quality_score = terms.quality_score;
+    escrow
+}
+
+/// An instruction acting on the escrow.
+#[derive(Clone, Debug)]
+enum Op {
+    /// Claim `at` seconds after creation.
+    Release { at: i64 },
+    /// Return everything to the sender.
+    Refund,
+    /// Freeze the escrow for moderation.
+    Dispute,
+    /// Return a disputed or claiming escrow to Pending.
+    Reopen,
+    /// Close an expired escrow to claims.
+    Expire,
+    /// Start a two-phase release.
+    BeginClaim,
+    /// Push the expiry back by `seconds`.
+    Extend { seconds: i64 },
+}
+
+impl Op {
+    fn target(&self) -> EscrowStatus {
+        match self {
+            Op::Release { .. } => EscrowStatus::Completed,
+            Op::Refund => EscrowStatus::Refunded,
+            Op::Dispute => EscrowStatus::Disputed,
+            Op::Expire => EscrowStatus::Expired,
+            Op::BeginClaim => EscrowStatus::Claiming,
+            Op::Reopen | Op::Extend { .. } => EscrowStatus::Pending,
+        }
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..=200 * DAY).prop_map(|at| Op::Release { at }),
+        Just(Op::Refund),
+        Just(Op::Dispute),
+        Just(Op::Reopen),
+        Just(Op::Expire),
+        Just(Op::BeginClaim),
+        (-DAY..=120 * DAY).prop_map(|seconds| Op::Extend { seconds }),
+    ]
+}
+
+/// Lamports held by each party of a single escrow.
+struct Ledger {
+    escrow: Escrow,
+    sender: u64,
+    receiver: u64,
+    held: u64,
+    settlements: u32,
+}
+
+impl Ledger {
+    fn open(terms: &Terms) -> Self {
+        let escrow = open_escrow(terms);
+        let held = escrow.pending_amount().unwrap();
+
+        Ledger {
+            escrow,
+            sender: 0,
+            receiver: 0,
+            held,
+            settlements: 0,
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.escrow.status == EscrowStatus::Completed
+            || self.escrow.status == EscrowStatus::Refunded
+    }
+
+    fn apply(&mut self, op: &Op) -> Result<(), TestCaseError> {
+        if let Op::Extend { seconds } = op {
+            return self.extend(*seconds);
+        }
+
+        let before = self.escrow.status.clone();
+        match self.escrow.status.transition_to(op.target()) {
+            Ok(()) => match op {
+                Op::Release { at } => self.release(NOW + at)?,
+                Op::Refund => {
+                    self.sender += self.held;
+                    self.held = 0;
+                    self.settlements += 1;
+                }
+                _ => {}
+            },
+            Err(err) => {
+                prop_assert_eq!(err, EscrowError::InvalidStatus.into());
+                prop_assert!(self.escrow.status == before, "rejected transition changed status");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Split the held lamports between sender and receiver as a claim at `now` does.
+    fn release(&mut self, now: i64) -> Result<(), TestCaseError> {
+        let escrow = &self.escrow;
+        let payout = self.held;
+        let base_payout = payout - escrow.bonus;
+
+        let late_haircut = escrow.late_haircut(base_payout, now).unwrap();
+        let quality_slash = escrow.quality_slash(base_payout - late_haircut).unwrap();
+        let refund_share = escrow.refund_share(payout, now).unwrap();
+        let forfeited_bonus = escrow.forfeited_bonus(now);
+
+        prop_assert!(refund_share <= base_payout);
+        prop_assert_eq!(refund_share, late_haircut + quality_slash);
+        prop_assert_eq!(forfeited_bonus, if now > escrow.bonus_deadline { escrow.bonus } else { 0 });
+        let sender_share = refund_share + forfeited_bonus;
+
+        self.sender += sender_share;
+        self.receiver += payout - sender_share;
+        self.held = 0;
+        self.settlements += 1;
+
+        Ok(())
+    }
+
+    /// Extend the expiry; the instruction only accepts pending escrows.
+    fn extend(&mut self, seconds: i64) -> Result<(), TestCaseError> {
+        if self.escrow.status != EscrowStatus::Pending {
+            return Ok(());
+        }
+
+        let expires_at = self.escrow.expires_at;
+        match self.escrow.extend(seconds) {
+            Ok(()) => {
+                prop_assert!(self.escrow.expires_at > expires_at);
+                prop_assert!(self.escrow.expires_at - self.escrow.created_at <= 180 * DAY);
+            }
+            Err(err) => {
+                prop_assert_eq!(err, EscrowError::InvalidTtl.into());
+                prop_assert_eq!(self.escrow.expires_at, expires_at);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+proptest! {
+    #[test]
+    fn lamports_are_conserved_and_settle_once(
+        terms in terms(),
+        ops in prop::collection::vec(op(), 0..32),
+    ) {
+        let mut ledger = Ledger::open(&terms);
+        let total = terms.amount + terms.bonus;
+
+        for op in &ops {
+            let was_terminal = ledger.is_terminal();
+            ledger.apply(op)?;
+
+            prop_assert_eq!(ledger.sender + ledger.receiver + ledger.held, total);
+            prop_assert!(ledger.settlements <= 1);
+            prop_assert_eq!(ledger.held == 0, ledger.is_terminal());
+            if was_terminal {
+                prop_assert!(ledger.held == 0 && ledger.settlements == 1);
+            }
+        }
+    }
+
+    #[test]
+    fn refunded_escrows_never_pay_the_receiver(
+        terms in terms(),
+        ops in prop::collection::vec(op(), 0..32),
+    ) {
+        let mut ledger = Ledger::open(&terms);
+
+        for op in &ops {
+            ledger.apply(op)?;
+            if ledger.escrow.status == EscrowStatus::Refunded {
+                prop_assert_eq!(ledger.receiver, 0);
+                prop_assert_eq!(ledger.sender, terms.amount + terms.bonus);
+            }
+        }
+    }
+
+    #[test]
+    fn timely_replies_meeting_the_bar_pay_the_receiver_everything(
+        terms in terms(),
+        at in 0..=90 * DAY,
+    ) {
+        let mut ledger = Ledger::open(&terms);
+        ledger.escrow.quality_score = ledger.escrow.quality_bar;
+        let at = at.min(terms.reply_window).min(terms.bonus_window);
+
+        ledger.apply(&Op::Release { at })?;
+
+        prop_assert_eq!(ledger.sender, 0);
+        prop_assert_eq!(ledger.receiver, terms.amount + terms.bonus);
+    }
+}
+
+#[test]
+fn terminal_statuses_are_absorbing() {
+    for terminal in [EscrowStatus::Completed, EscrowStatus::Refunded] {
+        for next in &ALL_STATUSES {
+            assert!(!terminal.can_transition_to(next));
+        }
+    }
+}
+
+#[test]
+fn every_open_status_can_settle() {
+    for start in &ALL_STATUSES {
+        let mut reachable = vec![start.clone()];
+        let mut frontier = vec![start.clone()];
+        while let Some(status) = frontier.pop() {
+            for next in &ALL_STATUSES {
+                if status.can_transition_to(next) && !reachable.contains(next) {
+                    reachable.push(next.clone());
+                    frontier.push(next.clone());
+                }
+            }
+        }
+
+        assert!(
+            reachable.contains(&EscrowStatus::Completed)
+                || reachable.contains(&EscrowStatus::Refunded),
+            "funds can get stuck"
+        );
+    }
+}